@@ -0,0 +1,206 @@
+//! Matching the host a request is made to against patterns like `{tenant}.example.com`
+
+use crate::param::ParamType;
+use crate::segment::{param_groups, param_spec, params_regex, split_pieces};
+use crate::Error;
+use regex::Regex;
+
+/// The values of a host's parameters, by name
+pub type HostParams<'p, 'h> = Vec<(&'p str, &'h str)>;
+
+/// A pattern for the host a request is made to, like `api.example.com` or
+/// `{tenant}.example.com`
+///
+/// Parameters match a single label, unless they're given a regex (`{name:regex}`).
+/// Their values aren't converted, so they can't be declared with any type but `str`.
+/// Hosts are matched case-insensitively, and without their port unless the pattern
+/// has one.
+#[derive(Debug)]
+pub struct HostPattern {
+    /// The pattern with its parameters' names left out, to compare patterns by
+    shape: String,
+    regex: Regex,
+    names: Vec<String>,
+    /// Index of the capture group for each parameter, in order
+    groups: Vec<usize>,
+    has_port: bool,
+}
+
+impl HostPattern {
+    /// Parse a host pattern
+    pub fn new(pattern: &str) -> Result<Self, Error> {
+        let params = split_pieces(pattern, |_| true);
+        let mut names = Vec::with_capacity(params.len());
+        let mut constraints = Vec::with_capacity(params.len());
+        for &(_, _, full) in &params {
+            let name = full.split(':').next().unwrap_or(full);
+            let constraint = match param_spec(full) {
+                None | Some("str") => "[^.]+",
+                Some(ty) if ParamType::from_name(ty).is_some() => {
+                    return Err(Error(format!(
+                        "parameter {:?} can't be of type {}, only str or a regex",
+                        name, ty
+                    )))
+                }
+                Some(spec) => spec,
+            };
+            names.push(String::from(name));
+            constraints.push(constraint);
+        }
+        let regex = format!(
+            "(?i){}",
+            params_regex(pattern, &params, constraints.iter().copied())
+        );
+        let regex = Regex::new(&regex).map_err(|e| Error(e.to_string()))?;
+        // With a digit for each parameter, so `example.com:{port}` has a port too
+        let mut example = String::new();
+        let mut shape = String::new();
+        let mut last_end = 0;
+        for (&(start, end, _), constraint) in params.iter().zip(&constraints) {
+            example.push_str(&pattern[last_end..start]);
+            example.push('0');
+            shape.push_str(&pattern[last_end..start].to_ascii_lowercase());
+            shape.push_str(&format!("{{:{}}}", constraint));
+            last_end = end;
+        }
+        example.push_str(&pattern[last_end..]);
+        shape.push_str(&pattern[last_end..].to_ascii_lowercase());
+        Ok(Self {
+            shape,
+            groups: param_groups(&regex, names.len()),
+            regex,
+            names,
+            has_port: split_port(&example).1.is_some(),
+        })
+    }
+
+    /// Match `host`, returning the value of each parameter if it matches
+    pub fn match_host<'h>(&self, host: &'h str) -> Option<HostParams<'_, 'h>> {
+        let host = if self.has_port {
+            host
+        } else {
+            split_port(host).0
+        };
+        let captures = self.regex.captures(host)?;
+        let params = self
+            .names
+            .iter()
+            .zip(&self.groups)
+            .map(|(name, &i)| (name.as_str(), captures.get(i).map_or("", |m| m.as_str())))
+            .collect();
+        Some(params)
+    }
+}
+
+impl PartialEq for HostPattern {
+    fn eq(&self, other: &Self) -> bool {
+        self.shape == other.shape
+    }
+}
+
+/// Split the port off `host`, if it has one
+fn split_port(host: &str) -> (&str, Option<&str>) {
+    match host.rsplit_once(':') {
+        Some((host, port)) if !port.is_empty() && port.bytes().all(|b| b.is_ascii_digit()) => {
+            (host, Some(port))
+        }
+        _ => (host, None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matches(pattern: &str, host: &str) -> Option<Vec<(String, String)>> {
+        let pattern = HostPattern::new(pattern).unwrap();
+        let params = pattern.match_host(host)?;
+        Some(
+            params
+                .into_iter()
+                .map(|(name, value)| (String::from(name), String::from(value)))
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn literal_hosts() {
+        assert_eq!(matches("example.com", "example.com"), Some(vec![]));
+        assert_eq!(matches("Example.COM", "example.com"), Some(vec![]));
+        assert_eq!(matches("example.com", "EXAMPLE.com"), Some(vec![]));
+        // Dots aren't wildcards
+        assert_eq!(matches("example.com", "exampleXcom"), None);
+        assert_eq!(matches("example.com", "api.example.com"), None);
+        assert_eq!(matches("example.com", "example.com.evil"), None);
+    }
+
+    #[test]
+    fn params() {
+        let tenant = |host| matches("{tenant}.example.com", host);
+        assert_eq!(
+            tenant("acme.example.com"),
+            Some(vec![(String::from("tenant"), String::from("acme"))])
+        );
+        // A parameter is a single label
+        assert_eq!(tenant("a.b.example.com"), None);
+        assert_eq!(tenant(".example.com"), None);
+        assert_eq!(
+            matches("{sub:str}-{env}.example.com", "api-prod.example.com"),
+            Some(vec![
+                (String::from("sub"), String::from("api")),
+                (String::from("env"), String::from("prod")),
+            ])
+        );
+    }
+
+    #[test]
+    fn regex_params() {
+        let env = |host| matches("{env:(dev|staging)}.example.com", host);
+        assert_eq!(
+            env("staging.example.com"),
+            Some(vec![(String::from("env"), String::from("staging"))])
+        );
+        assert_eq!(env("prod.example.com"), None);
+        assert_eq!(
+            matches("{sub:.+}.example.com", "a.b.example.com"),
+            Some(vec![(String::from("sub"), String::from("a.b"))])
+        );
+        assert!(HostPattern::new("{id:int}.example.com").is_err());
+        assert!(HostPattern::new("{id:(}.example.com").is_err());
+    }
+
+    #[test]
+    fn ports() {
+        // Ignored unless the pattern has one
+        assert_eq!(matches("example.com", "example.com:8000"), Some(vec![]));
+        assert_eq!(
+            matches("{sub}.example.com", "api.example.com:80").map(|p| p.len()),
+            Some(1)
+        );
+        assert_eq!(
+            matches("example.com:8000", "example.com:8000"),
+            Some(vec![])
+        );
+        assert_eq!(matches("example.com:8000", "example.com:9000"), None);
+        assert_eq!(matches("example.com:8000", "example.com"), None);
+        assert_eq!(
+            matches("example.com:{port}", "example.com:8000"),
+            Some(vec![(String::from("port"), String::from("8000"))])
+        );
+        // Not a port
+        assert_eq!(matches("example.com", "example.com:"), None);
+    }
+
+    #[test]
+    fn equality_ignores_case_and_param_names() {
+        let pattern = |pattern| HostPattern::new(pattern).unwrap();
+        assert_eq!(pattern("API.example.com"), pattern("api.example.com"));
+        assert_eq!(pattern("{a}.example.com"), pattern("{b:str}.Example.com"));
+        assert_eq!(
+            pattern("{a:(x|y)}.example.com"),
+            pattern("{b:(x|y)}.example.com")
+        );
+        assert_ne!(pattern("{a}.example.com"), pattern("{a:.+}.example.com"));
+        assert_ne!(pattern("{a}.example.com"), pattern("{a}.example.org"));
+    }
+}
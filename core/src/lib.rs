@@ -0,0 +1,1058 @@
+//! The routing core of `starlite_router`, matching paths against routes without
+//! depending on Python
+//!
+//! A [`Router`] maps route paths like `/users/{id:int}` to values of any type. The
+//! Python extension stores its handlers in one, and Rust services can use it to route
+//! requests exactly the same way. With the `tower` feature, the `service` module routes
+//! `http::Request`s to a `tower::Service` for each route.
+//!
+//! ```
+//! use starlite_router_core::Router;
+//!
+//! let mut router = Router::default();
+//! router.insert("/users/{id:int}", "user").unwrap();
+//! router.insert("/users/me", "me").unwrap();
+//!
+//! let found = router.find("/users/42").unwrap();
+//! assert_eq!(found.route.value, "user");
+//! assert_eq!(found.param("id"), Some("42"));
+//! assert_eq!(router.find("/users/me").unwrap().route.value, "me");
+//! assert!(router.find("/users/abc").is_none());
+//! ```
+
+use ahash::AHashMap as HashMap;
+use percent_encoding::utf8_percent_encode;
+use std::borrow::Cow;
+use std::collections::hash_map::Entry;
+use std::fmt;
+
+pub mod host;
+pub mod negotiate;
+pub mod normalize;
+pub mod param;
+pub mod segment;
+#[cfg(feature = "tower")]
+pub mod service;
+pub mod version;
+
+use normalize::RawPath;
+use param::ParamType;
+use segment::{Segment, SegmentPattern};
+
+/// Why a route can't be added or looked up
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Error(String);
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// How paths with and without a trailing slash are treated
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TrailingSlash {
+    /// `/a/` and `/a` are the same route
+    #[default]
+    Lenient,
+    /// `/a/` and `/a` are distinct routes
+    Strict,
+    /// Like `Strict`, but an http request which only matches a route once its trailing
+    /// slash is added or removed is redirected there, with this status code
+    Redirect(u16),
+}
+
+impl TrailingSlash {
+    /// The mode called `name`: `"lenient"`, `"strict"`, `"redirect"` or
+    /// `"redirect_permanent"`
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "lenient" => Some(Self::Lenient),
+            "strict" => Some(Self::Strict),
+            "redirect" => Some(Self::Redirect(307)),
+            "redirect_permanent" => Some(Self::Redirect(308)),
+            _ => None,
+        }
+    }
+
+    /// The path routes are registered and looked up by
+    pub fn route_path(self, path: &str) -> &str {
+        let path = match self {
+            Self::Lenient => path.strip_suffix('/').unwrap_or(path),
+            Self::Strict | Self::Redirect(_) => path,
+        };
+        if path.is_empty() {
+            "/"
+        } else {
+            path
+        }
+    }
+
+    /// Split a path into the components routes are matched by
+    ///
+    /// Unless lenient, a trailing slash is kept as an empty final component.
+    fn components(self, path: &str) -> Vec<&str> {
+        let trailing_slash = path.len() > 1 && path.ends_with('/');
+        self.with_trailing_slash(split_path(path).collect(), trailing_slash)
+    }
+
+    /// Add the empty final component for a trailing slash to `segments`, unless lenient
+    fn with_trailing_slash(self, mut segments: Vec<&str>, trailing_slash: bool) -> Vec<&str> {
+        if self != Self::Lenient && trailing_slash {
+            segments.push("");
+        }
+        segments
+    }
+}
+
+/// `path` with its trailing slash removed if it has one, or added if it doesn't
+pub fn toggle_trailing_slash(path: &str) -> Option<String> {
+    match path.strip_suffix('/') {
+        Some("") => None,
+        Some(path) => Some(String::from(path)),
+        None => Some(format!("{}/", path)),
+    }
+}
+
+/// The location to redirect a request for `path` to, given the path with its
+/// trailing slash toggled, `other`
+///
+/// It's built from the non-empty segments of `other`, percent-encoded, so a path like
+/// `//evil.com/` or `/\evil.com/` can't be redirected to another host.
+fn redirect_location(other: &str) -> String {
+    let segments: Vec<String> = split_path(other)
+        .map(|segment| utf8_percent_encode(segment, segment::SEGMENT).to_string())
+        .collect();
+    let mut location = format!("/{}", segments.join("/"));
+    if other.ends_with('/') && !segments.is_empty() {
+        location.push('/');
+    }
+    location
+}
+
+/// The non-empty segments of a path
+pub fn split_path(path: &str) -> impl Iterator<Item = &'_ str> {
+    path.split('/').filter(|s| !s.is_empty())
+}
+
+/// Split the prefix of a mount into its segments, which must all be literal
+pub fn literal_prefix(prefix: &str) -> Result<Vec<&str>, Error> {
+    if !segment::split_pieces(prefix, |_| true).is_empty() {
+        return Err(Error(format!(
+            "Prefix {:?} can't contain path parameters",
+            prefix
+        )));
+    }
+    Ok(split_path(prefix).collect())
+}
+
+/// A route, with the value it was added with
+#[derive(Debug)]
+pub struct Route<T> {
+    /// The path the route was first added with
+    path: String,
+    /// Names of the route's parameters, in the order they appear in its path
+    param_names: Vec<String>,
+    /// The type of each parameter, which its value must be valid for
+    param_types: Vec<ParamType>,
+    /// If this is a mount point, handling any path below it, the path it's mounted at
+    mount_path: Option<String>,
+    pub value: T,
+}
+
+impl<T> Route<T> {
+    fn new(path: &str, is_param: &dyn Fn(&str) -> bool, value: T) -> Self {
+        let (param_names, param_types) = Self::params(path, is_param).into_iter().unzip();
+        Self {
+            path: String::from(path),
+            param_names,
+            param_types,
+            mount_path: None,
+            value,
+        }
+    }
+
+    /// The name and type of each parameter in `path`
+    ///
+    /// Parameters without a type, or constrained by a regex instead, are strings.
+    fn params(path: &str, is_param: &dyn Fn(&str) -> bool) -> Vec<(String, ParamType)> {
+        segment::split_pieces(path, is_param)
+            .into_iter()
+            .map(|(_, _, full)| {
+                let name = String::from(full.split(':').next().unwrap_or(full));
+                let ty = segment::param_spec(full).and_then(ParamType::from_name);
+                (name, ty.unwrap_or(ParamType::Str))
+            })
+            .collect()
+    }
+
+    /// Whether the route's parameters are exactly `params`
+    fn has_params(&self, params: &[(String, ParamType)]) -> bool {
+        let ours = self.param_names.iter().zip(&self.param_types);
+        params.len() == self.param_names.len()
+            && params
+                .iter()
+                .zip(ours)
+                .all(|((name, ty), (our_name, our_ty))| name == our_name && ty == our_ty)
+    }
+
+    /// Whether each of `params` is valid for the type of its parameter
+    fn accepts(&self, params: &[Cow<'_, str>]) -> bool {
+        self.param_types
+            .iter()
+            .zip(params)
+            .all(|(ty, value)| ty.accepts(value))
+    }
+
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    pub fn param_names(&self) -> &[String] {
+        &self.param_names
+    }
+
+    pub fn is_mount(&self) -> bool {
+        self.mount_path.is_some()
+    }
+
+    /// Stop handling paths below this route, once it isn't a mount anymore
+    pub fn unmount(&mut self) {
+        self.mount_path = None;
+    }
+
+    /// If this is a mount point, split `path` into the mount's prefix and the rest of
+    /// the path below it, which always starts with a `/`
+    pub fn split_mount<'p>(&self, path: &'p str) -> Option<(&'p str, &'p str)> {
+        let mount_path = self.mount_path.as_deref()?;
+        let mut rest = path;
+        for segment in split_path(mount_path) {
+            rest = rest.trim_start_matches('/').strip_prefix(segment)?;
+            if !(rest.is_empty() || rest.starts_with('/')) {
+                return None;
+            }
+        }
+        let prefix = &path[..path.len() - rest.len()];
+        match rest {
+            _ if prefix.is_empty() => None,
+            "" => Some((prefix, "/")),
+            rest => Some((prefix, rest)),
+        }
+    }
+}
+
+/// The route found for a path, with the raw values of its parameters
+#[derive(Debug)]
+pub struct Match<'r, 'p, T> {
+    pub route: &'r Route<T>,
+    /// In the order the parameters appear in the route's path
+    pub params: Vec<Cow<'p, str>>,
+}
+
+impl<T> Match<'_, '_, T> {
+    /// The value of the parameter called `name`
+    pub fn param(&self, name: &str) -> Option<&str> {
+        let i = self.route.param_names.iter().position(|n| n == name)?;
+        self.params.get(i).map(|value| value.as_ref())
+    }
+}
+
+/// Routes, which paths are matched against
+///
+/// Routes without parameters are kept in a map by their path, the rest in a trie of
+/// their segments. Paths are matched by the plain routes first.
+#[derive(Debug)]
+pub struct Router<T> {
+    trailing_slash: TrailingSlash,
+    param_routes: Node<T>,
+    plain_routes: HashMap<String, Route<T>>,
+}
+
+impl<T> Default for Router<T> {
+    fn default() -> Self {
+        Self::new(TrailingSlash::default())
+    }
+}
+
+impl<T> Router<T> {
+    pub fn new(trailing_slash: TrailingSlash) -> Self {
+        Self {
+            trailing_slash,
+            param_routes: Node::default(),
+            plain_routes: HashMap::default(),
+        }
+    }
+
+    pub fn trailing_slash(&self) -> TrailingSlash {
+        self.trailing_slash
+    }
+
+    /// Add a route at `path`, returning the value it replaces if there's already a
+    /// route there
+    ///
+    /// Anything in braces in `path` is a parameter. Fails if a route with different
+    /// parameters is already where this one goes, like `/users/{id:int}` for
+    /// `/users/{name}`.
+    pub fn insert(&mut self, path: &str, value: T) -> Result<Option<T>, Error> {
+        let mut value = Some(value);
+        let route = self.get_or_insert_with(path, |_| true, false, || value.take().unwrap())?;
+        Ok(value.map(|value| std::mem::replace(&mut route.value, value)))
+    }
+
+    /// Mount `value` at `prefix`, to handle any path below it
+    pub fn mount(&mut self, prefix: &str, value: T) -> Result<(), Error> {
+        let path = format!("/{}", literal_prefix(prefix)?.join("/"));
+        if self.get(&path)?.is_some() {
+            return Err(Error(format!(
+                "Can't mount an app at {:?}, a route is already registered there",
+                path
+            )));
+        }
+        self.get_or_insert_with(&path, |_| false, true, || value)?;
+        Ok(())
+    }
+
+    /// Get the route at `path`, adding it with `value` if there isn't one yet
+    ///
+    /// Only `{...}` for which `is_param` is true are parameters, anything else in
+    /// braces is literal text. If `mount` is set, the route becomes a mount point.
+    pub fn get_or_insert_with(
+        &mut self,
+        path: &str,
+        is_param: impl Fn(&str) -> bool,
+        mount: bool,
+        value: impl FnOnce() -> T,
+    ) -> Result<&mut Route<T>, Error> {
+        self.check_insert(path, &is_param, mount)?;
+        let trailing_slash = self.trailing_slash;
+        let segments = self.segments(path, &is_param)?;
+        let has_params = segments
+            .iter()
+            .any(|segment| !matches!(segment, Segment::Literal(_)));
+        let route: &mut Route<T> = if has_params || mount {
+            let mut node = &mut self.param_routes;
+            for segment in segments {
+                node = match segment {
+                    Segment::Literal(s) => node.children.entry(String::from(s)).or_default(),
+                    Segment::Placeholder => {
+                        node.placeholder_child.get_or_insert_with(Default::default)
+                    }
+                    Segment::CatchAll => node.catch_all_child.get_or_insert_with(Default::default),
+                    Segment::Pattern(pattern) => node.pattern_child(pattern),
+                };
+            }
+            // Found where the route should be, get it, or add a new one
+            node.route
+                .get_or_insert_with(|| Route::new(path, &is_param, value()))
+        } else {
+            match self
+                .plain_routes
+                .entry(String::from(trailing_slash.route_path(path)))
+            {
+                Entry::Occupied(entry) => entry.into_mut(),
+                Entry::Vacant(entry) => entry.insert(Route::new(path, &is_param, value())),
+            }
+        };
+        if mount {
+            route.mount_path = Some(String::from(path));
+        }
+        Ok(route)
+    }
+
+    /// Check that [`Router::get_or_insert_with`] can add a route at `path`, without
+    /// changing anything, returning the route already there if there is one
+    pub fn check_insert(
+        &self,
+        path: &str,
+        is_param: impl Fn(&str) -> bool,
+        mount: bool,
+    ) -> Result<Option<&Route<T>>, Error> {
+        if let TrailingSlash::Redirect(_) = self.trailing_slash {
+            // Either route would make redirecting to the other impossible
+            if let Some(other) = toggle_trailing_slash(path) {
+                if self.get_by_shape(&other)?.is_some() {
+                    return Err(differ_by_trailing_slash(path, &other));
+                }
+            }
+        }
+
+        let segments = self.segments(path, &is_param)?;
+        let has_params = segments
+            .iter()
+            .any(|segment| !matches!(segment, Segment::Literal(_)));
+        if !(has_params || mount) {
+            let segments: Vec<&str> = split_path(path).collect();
+            if self.param_routes.is_mount(&segments) {
+                return Err(Error(format!(
+                    "Route {:?} conflicts with the app mounted there",
+                    path
+                )));
+            }
+            let route_path = self.trailing_slash.route_path(path);
+            return Ok(self.plain_routes.get(route_path));
+        }
+        if let Some(i) = segments.iter().position(|s| matches!(s, Segment::CatchAll)) {
+            if i != segments.len() - 1 {
+                return Err(Error(String::from(
+                    "Path parameters of type `path` must be the last segment",
+                )));
+            }
+        }
+        let route = self.param_routes.get(&segments);
+        if let Some(route) = route {
+            if !route.has_params(&Route::<T>::params(path, &is_param)) {
+                return Err(Error(format!(
+                    "Routes {:?} and {:?} have conflicting path parameters",
+                    path, route.path
+                )));
+            }
+        }
+        Ok(route)
+    }
+
+    /// Get the route added at `path`
+    ///
+    /// Its parameters must have the same names and types as those in `path`, not just
+    /// be in the same places.
+    pub fn get(&self, path: &str) -> Result<Option<&Route<T>>, Error> {
+        let route = self.get_by_shape(path)?;
+        Ok(route.filter(|route| self.is_added_at(route, path)))
+    }
+
+    /// Get the route added at `path`, to change it
+    pub fn get_mut(&mut self, path: &str) -> Result<Option<&mut Route<T>>, Error> {
+        let route_path = self.trailing_slash.route_path(path);
+        let route = if self.plain_routes.contains_key(route_path) {
+            self.plain_routes.get_mut(route_path)
+        } else {
+            let segments = self.template_segments(path)?;
+            self.param_routes.get_mut(&segments)
+        };
+        let trailing_slash = self.trailing_slash;
+        Ok(route.filter(|route| {
+            trailing_slash.route_path(&route.path) == trailing_slash.route_path(path)
+        }))
+    }
+
+    /// Remove the route added at `path`
+    pub fn remove(&mut self, path: &str) -> Result<Option<Route<T>>, Error> {
+        if self.get(path)?.is_none() {
+            return Ok(None);
+        }
+        let route_path = self.trailing_slash.route_path(path);
+        if let Some(route) = self.plain_routes.remove(route_path) {
+            return Ok(Some(route));
+        }
+        let segments = self.template_segments(path)?;
+        Ok(self.param_routes.remove(&segments))
+    }
+
+    /// Get the route at the same place as `path` would be, whatever its parameters
+    fn get_by_shape(&self, path: &str) -> Result<Option<&Route<T>>, Error> {
+        let route_path = self.trailing_slash.route_path(path);
+        if let Some(route) = self.plain_routes.get(route_path) {
+            return Ok(Some(route));
+        }
+        let segments = self.template_segments(path)?;
+        Ok(self.param_routes.get(&segments))
+    }
+
+    /// Whether `route` was added at `path`, or at `path` with its trailing slash
+    /// toggled if that makes no difference
+    fn is_added_at(&self, route: &Route<T>, path: &str) -> bool {
+        self.trailing_slash.route_path(&route.path) == self.trailing_slash.route_path(path)
+    }
+
+    /// All the routes
+    pub fn routes(&self) -> Vec<&Route<T>> {
+        let mut routes: Vec<&Route<T>> = self.plain_routes.values().collect();
+        self.param_routes.routes(&mut routes);
+        routes
+    }
+
+    /// Whether `segments` is exactly the path of a mount
+    pub fn is_mount(&self, segments: &[&str]) -> bool {
+        self.param_routes.is_mount(segments)
+    }
+
+    /// When redirecting on trailing slashes, the path to redirect a request for `path`
+    /// to, if it only matches a route once its trailing slash is toggled
+    pub fn trailing_slash_redirect(&self, path: &str) -> Option<String> {
+        if !matches!(self.trailing_slash, TrailingSlash::Redirect(_)) {
+            return None;
+        }
+        let other = toggle_trailing_slash(path)?;
+        self.find(&other)?;
+        Some(redirect_location(&other))
+    }
+
+    /// Find the route matching `path`, along with the raw values of its parameters
+    pub fn find<'r, 'p>(&'r self, path: &'p str) -> Option<Match<'r, 'p, T>> {
+        let path = self.trailing_slash.route_path(path);
+        let components = self.trailing_slash.components(path);
+        self.find_components(Some(path), &components)
+    }
+
+    /// Find the route matching a path split into segments before they were decoded,
+    /// so its parameters can contain slashes
+    pub fn find_raw<'r, 'p>(&'r self, raw: &'p RawPath) -> Option<Match<'r, 'p, T>> {
+        let segments = raw.segments.iter().map(String::as_str).collect();
+        let components = self
+            .trailing_slash
+            .with_trailing_slash(segments, raw.trailing_slash);
+        // With slashes inside segments, the path isn't that of a plain route
+        let plain_path =
+            (!raw.has_slash_in_segment()).then(|| self.trailing_slash.route_path(&raw.path));
+        self.find_components(plain_path, &components)
+    }
+
+    /// Find the route matching `components`, or the plain route at `path`
+    fn find_components<'r, 'p>(
+        &'r self,
+        path: Option<&str>,
+        components: &[&'p str],
+    ) -> Option<Match<'r, 'p, T>> {
+        if let Some(route) = path.and_then(|path| self.plain_routes.get(path)) {
+            return Some(Match {
+                route,
+                params: Vec::new(),
+            });
+        }
+        let mut params = Vec::new();
+        let route = self.param_routes.find(components, &mut params)?;
+        Some(Match { route, params })
+    }
+
+    /// Split the path of a route into segments, without knowing its path parameters
+    fn template_segments<'p>(&self, path: &'p str) -> Result<Vec<Segment<'p>>, Error> {
+        // Anything in braces is a parameter
+        self.segments(path, &|_| true)
+    }
+
+    /// Split the path of a route into segments, with `{...}` for which `is_param` is
+    /// true as parameters
+    fn segments<'p>(
+        &self,
+        path: &'p str,
+        is_param: &dyn Fn(&str) -> bool,
+    ) -> Result<Vec<Segment<'p>>, Error> {
+        self.trailing_slash
+            .components(path)
+            .into_iter()
+            .map(|s| Segment::parse(s, is_param))
+            .collect()
+    }
+}
+
+fn differ_by_trailing_slash(path: &str, other: &str) -> Error {
+    Error(format!(
+        "Routes {:?} and {:?} differ only by a trailing slash",
+        path, other
+    ))
+}
+
+#[derive(Debug)]
+struct Node<T> {
+    children: HashMap<String, Node<T>>,
+    placeholder_child: Option<Box<Node<T>>>,
+    /// Children for segments with constrained or multiple parameters (`{name:regex}`,
+    /// `{name}.{ext}`), tried in the order they were registered
+    pattern_children: Vec<(SegmentPattern, Node<T>)>,
+    /// Child for a `{name:path}` parameter, which captures all remaining segments
+    catch_all_child: Option<Box<Node<T>>>,
+    route: Option<Route<T>>,
+}
+
+impl<T> Default for Node<T> {
+    fn default() -> Self {
+        Self {
+            children: HashMap::default(),
+            placeholder_child: None,
+            pattern_children: Vec::new(),
+            catch_all_child: None,
+            route: None,
+        }
+    }
+}
+
+impl<T> Node<T> {
+    /// Find the route matching `components`, collecting placeholder values into
+    /// `params`.
+    ///
+    /// Literal children are preferred over pattern children, then the unconstrained
+    /// placeholder child, then the catch-all child, and a mount
+    /// only matches once no deeper route does. If a branch dead-ends, or the route it
+    /// leads to doesn't accept the values of its typed parameters, the next
+    /// alternative is tried. Since this is a tree, each node can only be reached at
+    /// one depth, so a lookup visits every node at most once.
+    fn find<'a, 'p>(
+        &'a self,
+        components: &[&'p str],
+        params: &mut Vec<Cow<'p, str>>,
+    ) -> Option<&'a Route<T>> {
+        let (&component, rest) = match components.split_first() {
+            Some(split) => split,
+            None => return self.route.as_ref().filter(|route| route.accepts(params)),
+        };
+        if let Some(route) = self
+            .children
+            .get(component)
+            .and_then(|child| child.find(rest, params))
+        {
+            return Some(route);
+        }
+        // The empty component from a trailing slash can only match literally
+        if component.is_empty() {
+            return self.mount(params);
+        }
+        for (pattern, child) in &self.pattern_children {
+            let len = params.len();
+            if pattern.match_into(component, params) {
+                if let Some(route) = child.find(rest, params) {
+                    return Some(route);
+                }
+                params.truncate(len);
+            }
+        }
+        if let Some(child) = &self.placeholder_child {
+            params.push(Cow::Borrowed(component));
+            if let Some(route) = child.find(rest, params) {
+                return Some(route);
+            }
+            params.pop();
+        }
+        if let Some(route) = self
+            .catch_all_child
+            .as_ref()
+            .and_then(|child| child.route.as_ref())
+        {
+            params.push(Cow::Owned(components.join("/")));
+            if route.accepts(params) {
+                return Some(route);
+            }
+            params.pop();
+        }
+        self.mount(params)
+    }
+
+    /// This node's route, if it's a mount whose parameters accept `params`
+    fn mount(&self, params: &[Cow<'_, str>]) -> Option<&Route<T>> {
+        self.route
+            .as_ref()
+            .filter(|route| route.is_mount() && route.accepts(params))
+    }
+
+    /// Get the child for `segment`, if there is one
+    fn child(&self, segment: &Segment<'_>) -> Option<&Node<T>> {
+        match segment {
+            Segment::Literal(s) => self.children.get(*s),
+            Segment::Placeholder => self.placeholder_child.as_deref(),
+            Segment::CatchAll => self.catch_all_child.as_deref(),
+            Segment::Pattern(pattern) => self
+                .pattern_children
+                .iter()
+                .find(|(p, _)| p == pattern)
+                .map(|(_, child)| child),
+        }
+    }
+
+    fn child_mut(&mut self, segment: &Segment<'_>) -> Option<&mut Node<T>> {
+        match segment {
+            Segment::Literal(s) => self.children.get_mut(*s),
+            Segment::Placeholder => self.placeholder_child.as_deref_mut(),
+            Segment::CatchAll => self.catch_all_child.as_deref_mut(),
+            Segment::Pattern(pattern) => self
+                .pattern_children
+                .iter_mut()
+                .find(|(p, _)| p == pattern)
+                .map(|(_, child)| child),
+        }
+    }
+
+    /// Get the route added at exactly `segments`
+    fn get(&self, segments: &[Segment<'_>]) -> Option<&Route<T>> {
+        match segments.split_first() {
+            Some((segment, rest)) => self.child(segment)?.get(rest),
+            None => self.route.as_ref(),
+        }
+    }
+
+    fn get_mut(&mut self, segments: &[Segment<'_>]) -> Option<&mut Route<T>> {
+        match segments.split_first() {
+            Some((segment, rest)) => self.child_mut(segment)?.get_mut(rest),
+            None => self.route.as_mut(),
+        }
+    }
+
+    /// Collect the routes of this node and all its descendants
+    fn routes<'a>(&'a self, routes: &mut Vec<&'a Route<T>>) {
+        routes.extend(&self.route);
+        for child in self.children.values() {
+            child.routes(routes);
+        }
+        for (_, child) in &self.pattern_children {
+            child.routes(routes);
+        }
+        for child in self.placeholder_child.iter().chain(&self.catch_all_child) {
+            child.routes(routes);
+        }
+    }
+
+    /// Whether `segments` is exactly the path of a mount
+    fn is_mount(&self, segments: &[&str]) -> bool {
+        match segments.split_first() {
+            Some((segment, rest)) => self
+                .children
+                .get(*segment)
+                .is_some_and(|child| child.is_mount(rest)),
+            None => self.route.as_ref().is_some_and(Route::is_mount),
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.children.is_empty()
+            && self.placeholder_child.is_none()
+            && self.pattern_children.is_empty()
+            && self.catch_all_child.is_none()
+            && self.route.is_none()
+    }
+
+    /// Remove the route at `segments`, pruning any nodes left empty
+    fn remove(&mut self, segments: &[Segment<'_>]) -> Option<Route<T>> {
+        let (segment, rest) = match segments.split_first() {
+            Some(split) => split,
+            None => return self.route.take(),
+        };
+        match segment {
+            Segment::Literal(s) => {
+                let child = self.children.get_mut(*s)?;
+                let removed = child.remove(rest);
+                if child.is_empty() {
+                    self.children.remove(*s);
+                }
+                removed
+            }
+            Segment::Placeholder => Self::remove_from_box(&mut self.placeholder_child, rest),
+            Segment::CatchAll => Self::remove_from_box(&mut self.catch_all_child, rest),
+            Segment::Pattern(pattern) => {
+                let i = self
+                    .pattern_children
+                    .iter()
+                    .position(|(p, _)| p == pattern)?;
+                let removed = self.pattern_children[i].1.remove(rest);
+                if self.pattern_children[i].1.is_empty() {
+                    self.pattern_children.remove(i);
+                }
+                removed
+            }
+        }
+    }
+
+    fn remove_from_box(
+        child: &mut Option<Box<Node<T>>>,
+        segments: &[Segment<'_>],
+    ) -> Option<Route<T>> {
+        let node = child.as_mut()?;
+        let removed = node.remove(segments);
+        if node.is_empty() {
+            *child = None;
+        }
+        removed
+    }
+
+    /// Get the child for `pattern`, adding it if needed
+    fn pattern_child(&mut self, pattern: SegmentPattern) -> &mut Node<T> {
+        let i = match self
+            .pattern_children
+            .iter()
+            .position(|(p, _)| *p == pattern)
+        {
+            Some(i) => i,
+            None => {
+                self.pattern_children.push((pattern, Node::default()));
+                self.pattern_children.len() - 1
+            }
+        };
+        &mut self.pattern_children[i].1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn router(paths: &[&'static str]) -> Router<&'static str> {
+        let mut router = Router::default();
+        for path in paths {
+            router.insert(path, *path).unwrap();
+        }
+        router
+    }
+
+    /// The path of the route `path` matches, with its parameters' values
+    fn find<'r>(router: &'r Router<&'static str>, path: &str) -> Option<(&'r str, Vec<String>)> {
+        let found = router.find(path)?;
+        let params = found
+            .params
+            .iter()
+            .map(|p| String::from(p.as_ref()))
+            .collect();
+        Some((found.route.value, params))
+    }
+
+    #[test]
+    fn literal_before_placeholder() {
+        let router = router(&["/users/{id}", "/users/me"]);
+        assert_eq!(find(&router, "/users/me"), Some(("/users/me", vec![])));
+        assert_eq!(
+            find(&router, "/users/42"),
+            Some(("/users/{id}", vec![String::from("42")]))
+        );
+    }
+
+    #[test]
+    fn backtracks_from_dead_end_literal() {
+        let router = router(&["/users/{id}/settings", "/users/me", "/users/me/profile"]);
+        assert_eq!(
+            find(&router, "/users/me/settings"),
+            Some(("/users/{id}/settings", vec![String::from("me")]))
+        );
+        assert_eq!(
+            find(&router, "/users/me/profile"),
+            Some(("/users/me/profile", vec![]))
+        );
+        assert_eq!(find(&router, "/users/me/other"), None);
+    }
+
+    #[test]
+    fn pattern_before_placeholder() {
+        let router = router(&["/files/{name}", "/files/{name}.{ext}", "/n/{id:[0-9]+}"]);
+        assert_eq!(
+            find(&router, "/files/a.txt"),
+            Some((
+                "/files/{name}.{ext}",
+                vec![String::from("a"), String::from("txt")]
+            ))
+        );
+        assert_eq!(
+            find(&router, "/files/readme"),
+            Some(("/files/{name}", vec![String::from("readme")]))
+        );
+        assert_eq!(
+            find(&router, "/n/12"),
+            Some(("/n/{id:[0-9]+}", vec![String::from("12")]))
+        );
+        assert_eq!(find(&router, "/n/x"), None);
+    }
+
+    #[test]
+    fn placeholder_before_catch_all() {
+        let router = router(&["/a/{x}", "/a/{rest:path}", "/a/{x}/b"]);
+        assert_eq!(
+            find(&router, "/a/1"),
+            Some(("/a/{x}", vec![String::from("1")]))
+        );
+        assert_eq!(
+            find(&router, "/a/1/b"),
+            Some(("/a/{x}/b", vec![String::from("1")]))
+        );
+        assert_eq!(
+            find(&router, "/a/1/c/d"),
+            Some(("/a/{rest:path}", vec![String::from("1/c/d")]))
+        );
+    }
+
+    #[test]
+    fn typed_params_backtrack() {
+        let router = router(&[
+            "/items/{id:int}",
+            "/items/{rest:path}",
+            "/d/{day:date}.{ext}",
+        ]);
+        assert_eq!(
+            find(&router, "/items/42"),
+            Some(("/items/{id:int}", vec![String::from("42")]))
+        );
+        assert_eq!(
+            find(&router, "/items/abc"),
+            Some(("/items/{rest:path}", vec![String::from("abc")]))
+        );
+        assert_eq!(
+            find(&router, "/d/2024-01-31.json"),
+            Some((
+                "/d/{day:date}.{ext}",
+                vec![String::from("2024-01-31"), String::from("json")]
+            ))
+        );
+        assert_eq!(find(&router, "/d/2024-02-31.json"), None);
+    }
+
+    #[test]
+    fn conflicting_params() {
+        let mut router = router(&["/users/{id:int}"]);
+        assert!(router.insert("/users/{name}", "/users/{name}").is_err());
+        assert!(router
+            .insert("/users/{uid:int}", "/users/{uid:int}")
+            .is_err());
+        assert_eq!(
+            router.insert("/users/{id:int}", "again").unwrap(),
+            Some("/users/{id:int}")
+        );
+        assert_eq!(find(&router, "/users/1").unwrap().0, "again");
+    }
+
+    #[test]
+    fn check_insert() {
+        let mut router = router(&["/users/{id:int}", "/about"]);
+        router.mount("/static", "static").unwrap();
+        let check = |path| router.check_insert(path, |_| true, false);
+        assert_eq!(
+            check("/users/{id:int}").unwrap().unwrap().value,
+            "/users/{id:int}"
+        );
+        assert_eq!(check("/about").unwrap().unwrap().value, "/about");
+        assert!(check("/users/{id:int}/posts").unwrap().is_none());
+        assert!(check("/users/{name}").is_err());
+        assert!(check("/static").is_err());
+        assert!(check("/a/{rest:path}/b").is_err());
+        // Nothing was added
+        assert_eq!(router.routes().len(), 3);
+    }
+
+    #[test]
+    fn catch_all() {
+        let router = router(&["/static/{file:path}", "/static/index"]);
+        assert_eq!(
+            find(&router, "/static/index"),
+            Some(("/static/index", vec![]))
+        );
+        assert_eq!(
+            find(&router, "/static/css/site.css"),
+            Some(("/static/{file:path}", vec![String::from("css/site.css")]))
+        );
+        assert_eq!(find(&router, "/static"), None);
+        let mut router = Router::default();
+        assert!(router.insert("/a/{rest:path}/b", ()).is_err());
+    }
+
+    #[test]
+    fn patterns() {
+        let router = router(&["/v/{major:[0-9]+}.{minor:[0-9]+}", "/v/{tag}"]);
+        assert_eq!(
+            find(&router, "/v/1.2"),
+            Some((
+                "/v/{major:[0-9]+}.{minor:[0-9]+}",
+                vec![String::from("1"), String::from("2")]
+            ))
+        );
+        assert_eq!(
+            find(&router, "/v/1.x"),
+            Some(("/v/{tag}", vec![String::from("1.x")]))
+        );
+        let mut router = Router::default();
+        assert!(router.insert("/{a}{b}", ()).is_err());
+        assert!(router.insert("/{a:(}", ()).is_err());
+    }
+
+    #[test]
+    fn mounts() {
+        let mut router = router(&["/api/users"]);
+        router.mount("/api", "mount").unwrap();
+        assert_eq!(find(&router, "/api/users"), Some(("/api/users", vec![])));
+        assert_eq!(find(&router, "/api/other/x"), Some(("mount", vec![])));
+        assert_eq!(find(&router, "/api"), Some(("mount", vec![])));
+        let found = router.find("/api/other/x").unwrap();
+        assert_eq!(
+            found.route.split_mount("/api/other/x"),
+            Some(("/api", "/other/x"))
+        );
+        assert_eq!(found.route.split_mount("/api"), Some(("/api", "/")));
+        assert!(router.mount("/api/users", "again").is_err());
+        assert!(router.mount("/{x}", "param").is_err());
+        assert!(router.insert("/api", "route").is_err());
+    }
+
+    #[test]
+    fn trailing_slash_modes() {
+        let mut lenient = Router::new(TrailingSlash::Lenient);
+        lenient.insert("/a/", "a").unwrap();
+        assert_eq!(lenient.find("/a").unwrap().route.value, "a");
+        assert_eq!(lenient.find("/a/").unwrap().route.value, "a");
+        // The same route, with or without the slash
+        assert_eq!(lenient.insert("/a", "other").unwrap(), Some("a"));
+        assert_eq!(lenient.find("/a/").unwrap().route.value, "other");
+        lenient.insert("/p/{x}", "p").unwrap();
+        assert_eq!(lenient.insert("/p/{x}/", "p/").unwrap(), Some("p"));
+        assert_eq!(lenient.find("/p/1").unwrap().route.value, "p/");
+
+        let mut strict = Router::new(TrailingSlash::Strict);
+        strict.insert("/a", "a").unwrap();
+        strict.insert("/a/", "a/").unwrap();
+        strict.insert("/b/{x}/", "b").unwrap();
+        assert_eq!(strict.find("/a").unwrap().route.value, "a");
+        assert_eq!(strict.find("/a/").unwrap().route.value, "a/");
+        assert_eq!(strict.find("/b/1/").unwrap().route.value, "b");
+        assert!(strict.find("/b/1").is_none());
+
+        let mut redirect = Router::new(TrailingSlash::Redirect(307));
+        redirect.insert("/a", "a").unwrap();
+        assert!(redirect.insert("/a/", "a/").is_err());
+        assert!(redirect.find("/a/").is_none());
+    }
+
+    #[test]
+    fn trailing_slash_redirects() {
+        let mut router = Router::new(TrailingSlash::Redirect(307));
+        router.insert("/{slug}", "slug").unwrap();
+        router.insert("/a/b/", "a/b/").unwrap();
+        assert_eq!(router.trailing_slash_redirect("/x/").as_deref(), Some("/x"));
+        assert_eq!(
+            router.trailing_slash_redirect("/a/b").as_deref(),
+            Some("/a/b/")
+        );
+        assert_eq!(router.trailing_slash_redirect("/a/c"), None);
+        // Never to another host
+        assert_eq!(
+            router.trailing_slash_redirect("//evil.com/").as_deref(),
+            Some("/evil.com")
+        );
+        assert_eq!(
+            router.trailing_slash_redirect("/\\evil.com/").as_deref(),
+            Some("/%5Cevil.com")
+        );
+        assert_eq!(Router::<()>::default().trailing_slash_redirect("/x/"), None);
+    }
+
+    #[test]
+    fn remove_prunes() {
+        let mut router = router(&["/a/{x:int}/b", "/a/{x:int}/c", "/plain"]);
+        assert_eq!(
+            router.remove("/a/{x:int}/b").unwrap().unwrap().value,
+            "/a/{x:int}/b"
+        );
+        assert_eq!(find(&router, "/a/1/b"), None);
+        assert_eq!(find(&router, "/a/1/c").unwrap().0, "/a/{x:int}/c");
+        assert!(router.remove("/a/{x:int}/b").unwrap().is_none());
+        router.remove("/a/{x:int}/c").unwrap();
+        assert!(router.param_routes.is_empty());
+        // Nothing is left to conflict with
+        router.insert("/a/{name}/c", "/a/{name}/c").unwrap();
+        assert_eq!(router.remove("/plain").unwrap().unwrap().value, "/plain");
+        assert_eq!(router.routes().len(), 1);
+    }
+
+    #[test]
+    fn get_and_remove_by_exact_path() {
+        let mut router = router(&["/users/{id:int}", "/a/"]);
+        assert!(router.get("/users/{other}").unwrap().is_none());
+        assert!(router.get("/users/{id}").unwrap().is_none());
+        assert!(router.remove("/users/{other}").unwrap().is_none());
+        assert_eq!(find(&router, "/users/1").unwrap().0, "/users/{id:int}");
+        assert!(router.get_mut("/users/{id:int}").unwrap().is_some());
+        // Lenient, so the trailing slash doesn't matter
+        assert!(router.get("/a").unwrap().is_some());
+        assert!(router.remove("/users/{id:int}").unwrap().is_some());
+    }
+}
@@ -0,0 +1,250 @@
+//! Choosing between handlers by a request's headers, like `Accept`, and the
+//! subprotocols a websocket asks for
+
+use std::cmp::Reverse;
+
+/// Conditions on a request's headers for a handler to be chosen
+#[derive(Debug, Clone, Default)]
+pub struct HeaderPredicates {
+    /// One of these must be allowed by the `Accept` header, if there is one
+    pub accept: Vec<MediaType>,
+    /// The `Content-Type` header must be one of these
+    pub content_type: Vec<MediaType>,
+    /// Headers which must be present, with the given value if there is one, by their
+    /// lowercase name
+    pub headers: Vec<(String, Option<String>)>,
+    /// The websocket must ask for one of these subprotocols
+    pub subprotocols: Vec<String>,
+}
+
+/// How well a request matches some predicates, better matches comparing greater
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub struct Rank<'p> {
+    /// The subprotocol which matched, if any, preferring those the request lists
+    /// first
+    subprotocol: Option<(Reverse<usize>, &'p str)>,
+    /// From the request's `Accept` header
+    quality: f32,
+}
+
+impl<'p> Rank<'p> {
+    /// The websocket subprotocol the predicates were matched by
+    pub fn subprotocol(&self) -> Option<&'p str> {
+        self.subprotocol.map(|(_, subprotocol)| subprotocol)
+    }
+}
+
+impl HeaderPredicates {
+    /// How well a request with `headers`, asking for `subprotocols`, matches, or
+    /// `None` if it doesn't
+    ///
+    /// Requests which match several handlers get the one with the subprotocol they
+    /// prefer, then the best quality from the request's `Accept` header.
+    pub fn rank<'p>(
+        &'p self,
+        headers: &[(&[u8], &[u8])],
+        subprotocols: &[String],
+    ) -> Option<Rank<'p>> {
+        let subprotocol = if self.subprotocols.is_empty() {
+            None
+        } else {
+            let found = subprotocols.iter().enumerate().find_map(|(i, requested)| {
+                let ours = self.subprotocols.iter().find(|ours| *ours == requested)?;
+                Some((Reverse(i), ours.as_str()))
+            });
+            Some(found?)
+        };
+        let quality = self.quality(headers)?;
+        Some(Rank {
+            subprotocol,
+            quality,
+        })
+    }
+
+    fn quality(&self, headers: &[(&[u8], &[u8])]) -> Option<f32> {
+        for (name, value) in &self.headers {
+            let found = header_values(headers, name.as_bytes())
+                .any(|found| value.as_deref().is_none_or(|value| found == value));
+            if !found {
+                return None;
+            }
+        }
+
+        if !self.content_type.is_empty() {
+            let content_type = header_values(headers, b"content-type").next()?;
+            let content_type = MediaType::parse(content_type.split(';').next().unwrap_or(""));
+            if !self.content_type.iter().any(|ty| ty.covers(&content_type)) {
+                return None;
+            }
+        }
+
+        if self.accept.is_empty() {
+            return Some(1.0);
+        }
+        let ranges: Vec<(MediaType, f32)> = header_values(headers, b"accept")
+            .flat_map(|value| value.split(','))
+            .filter(|range| !range.trim().is_empty())
+            .map(parse_media_range)
+            .collect();
+        if ranges.is_empty() {
+            return Some(1.0);
+        }
+        let quality = self
+            .accept
+            .iter()
+            .filter_map(|ty| {
+                // The most specific range which covers the type decides its quality
+                ranges
+                    .iter()
+                    .filter(|(range, _)| range.covers(ty))
+                    .max_by_key(|(range, _)| range.specificity())
+                    .map(|&(_, quality)| quality)
+            })
+            .fold(0.0, f32::max);
+        (quality > 0.0).then_some(quality)
+    }
+}
+
+/// A media type, or a range of them with `*` wildcards
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MediaType {
+    ty: String,
+    subtype: String,
+}
+
+impl MediaType {
+    /// Parse a media type like `text/html`, or a range like `text/*`
+    pub fn parse(s: &str) -> Self {
+        let s = s.trim().to_ascii_lowercase();
+        let (ty, subtype) = s.split_once('/').unwrap_or((&s, "*"));
+        Self {
+            ty: String::from(ty.trim()),
+            subtype: String::from(subtype.trim()),
+        }
+    }
+
+    fn covers(&self, other: &MediaType) -> bool {
+        (self.ty == "*" || self.ty == other.ty)
+            && (self.subtype == "*" || self.subtype == other.subtype)
+    }
+
+    fn specificity(&self) -> u8 {
+        u8::from(self.ty != "*") + u8::from(self.subtype != "*")
+    }
+}
+
+/// Parse a media range from an `Accept` header, with its quality
+fn parse_media_range(range: &str) -> (MediaType, f32) {
+    let mut parts = range.split(';');
+    let ty = MediaType::parse(parts.next().unwrap_or(""));
+    let quality = parts
+        .filter_map(|param| param.split_once('='))
+        .find(|(name, _)| name.trim().eq_ignore_ascii_case("q"))
+        .and_then(|(_, value)| value.trim().parse().ok())
+        .unwrap_or(1.0);
+    (ty, quality)
+}
+
+/// The values of the headers named `name`
+fn header_values<'h>(
+    headers: &'h [(&[u8], &[u8])],
+    name: &'h [u8],
+) -> impl Iterator<Item = &'h str> {
+    headers
+        .iter()
+        .filter(move |(header, _)| header.eq_ignore_ascii_case(name))
+        .filter_map(|(_, value)| std::str::from_utf8(value).ok())
+        .map(str::trim)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn predicates(accept: &[&str], content_type: &[&str]) -> HeaderPredicates {
+        HeaderPredicates {
+            accept: accept.iter().map(|ty| MediaType::parse(ty)).collect(),
+            content_type: content_type.iter().map(|ty| MediaType::parse(ty)).collect(),
+            ..HeaderPredicates::default()
+        }
+    }
+
+    #[test]
+    fn media_ranges() {
+        let (ty, quality) = parse_media_range(" Text/HTML ; level=1; Q=0.7");
+        assert_eq!(
+            (ty.ty.as_str(), ty.subtype.as_str(), quality),
+            ("text", "html", 0.7)
+        );
+        let (ty, quality) = parse_media_range("text");
+        assert_eq!(
+            (ty.ty.as_str(), ty.subtype.as_str(), quality),
+            ("text", "*", 1.0)
+        );
+        assert_eq!(parse_media_range("*/*;q=nope").1, 1.0);
+
+        let json = MediaType::parse("application/json");
+        assert!(MediaType::parse("*/*").covers(&json));
+        assert!(MediaType::parse("application/*").covers(&json));
+        assert!(!MediaType::parse("text/*").covers(&json));
+        assert!(!json.covers(&MediaType::parse("application/*")));
+        assert_eq!(MediaType::parse("*/*").specificity(), 0);
+        assert_eq!(MediaType::parse("application/*").specificity(), 1);
+        assert_eq!(json.specificity(), 2);
+    }
+
+    #[test]
+    fn most_specific_range_decides() {
+        let json = predicates(&["application/json"], &[]);
+        let quality = |accept: &str| json.quality(&[(b"accept", accept.as_bytes())]);
+        assert_eq!(
+            quality("*/*;q=0.1, application/json;q=0.8, application/*;q=0.5"),
+            Some(0.8)
+        );
+        assert_eq!(quality("*/*;q=0.3, text/*"), Some(0.3));
+        assert_eq!(quality("text/html"), None);
+        // The best of the handler's types counts
+        let both = predicates(&["application/xml", "application/json"], &[]);
+        let accept: &[u8] = b"application/xml;q=0.2, application/json;q=0.9";
+        assert_eq!(both.quality(&[(b"accept", accept)]), Some(0.9));
+    }
+
+    #[test]
+    fn zero_quality_excludes() {
+        let json = predicates(&["application/json"], &[]);
+        let quality = |accept: &str| json.quality(&[(b"Accept", accept.as_bytes())]);
+        assert_eq!(quality("application/json;q=0, */*"), None);
+        assert_eq!(quality("application/*;q=0"), None);
+        assert_eq!(
+            quality("application/*;q=0, application/json;q=0.4"),
+            Some(0.4)
+        );
+    }
+
+    #[test]
+    fn missing_accept_accepts_anything() {
+        let json = predicates(&["application/json"], &[]);
+        assert_eq!(json.quality(&[]), Some(1.0));
+        assert_eq!(json.quality(&[(b"accept", b" ")]), Some(1.0));
+        assert_eq!(
+            predicates(&[], &[]).quality(&[(b"accept", b"text/html")]),
+            Some(1.0)
+        );
+    }
+
+    #[test]
+    fn content_type_ignores_parameters() {
+        let json = predicates(&[], &["application/json"]);
+        let quality =
+            |content_type: &str| json.quality(&[(b"content-type", content_type.as_bytes())]);
+        assert_eq!(quality("application/json"), Some(1.0));
+        assert_eq!(quality("Application/JSON; charset=utf-8"), Some(1.0));
+        assert_eq!(quality("text/plain; charset=application/json"), None);
+        assert_eq!(json.quality(&[]), None);
+        let any_text = predicates(&[], &["text/*"]);
+        assert_eq!(
+            any_text.quality(&[(b"content-type", b"text/csv;header=present")]),
+            Some(1.0)
+        );
+    }
+}
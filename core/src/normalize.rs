@@ -0,0 +1,344 @@
+use percent_encoding::percent_decode;
+use std::borrow::Cow;
+
+/// What is done with request paths before they're matched against routes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PathNormalization {
+    /// Match paths as they are
+    #[default]
+    PassThrough,
+    /// Resolve `.` and `..` segments and collapse repeated slashes
+    Normalize,
+    /// Refuse any path which isn't already normalized
+    Reject,
+}
+
+impl PathNormalization {
+    /// The mode called `name`: `"pass_through"`, `"normalize"` or `"reject"`
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "pass_through" => Some(Self::PassThrough),
+            "normalize" => Some(Self::Normalize),
+            "reject" => Some(Self::Reject),
+            _ => None,
+        }
+    }
+}
+
+/// Why a path can't be routed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PathError {
+    /// The path contains a NUL byte or an encoded slash, or isn't normalized when
+    /// rejecting such paths
+    Malformed,
+    /// A `..` segment leaves the root, or a mounted app
+    Traversal,
+}
+
+/// Normalize a request's `path`, according to `mode`
+///
+/// `raw_path` is the undecoded path from the scope, if any, which is only used to
+/// spot encoded slashes: once decoded, they can't be told apart from real ones.
+/// `is_mount` is called with the segments a `..` would leave, and the path is
+/// refused if they're the path of a mount.
+pub fn normalize_path<'p>(
+    mode: PathNormalization,
+    path: &'p str,
+    raw_path: Option<&[u8]>,
+    is_mount: impl Fn(&[&str]) -> bool,
+) -> Result<Cow<'p, str>, PathError> {
+    if mode == PathNormalization::PassThrough {
+        return Ok(Cow::Borrowed(path));
+    }
+    if path.contains('\0') || raw_path.is_some_and(has_encoded_slash) {
+        return Err(PathError::Malformed);
+    }
+
+    let components: Vec<&str> = path.split('/').collect();
+    let resolved = resolve_dots(&components, is_mount)?;
+    if !resolved.changed && path.starts_with('/') {
+        return Ok(Cow::Borrowed(path));
+    }
+    if mode == PathNormalization::Reject {
+        return Err(PathError::Malformed);
+    }
+    Ok(Cow::Owned(resolved.join()))
+}
+
+/// A request's path taken from its `raw_path`, split into segments before they were
+/// percent-decoded, so segments can contain slashes
+#[derive(Debug)]
+pub struct RawPath {
+    /// The decoded, and normalized, path
+    ///
+    /// If any segment contains a slash, this isn't the path the segments came from,
+    /// so it mustn't be given to a handler.
+    pub path: String,
+    pub segments: Vec<String>,
+    pub trailing_slash: bool,
+}
+
+impl RawPath {
+    /// Split `raw_path` on `/`, then percent-decode and normalize its segments
+    ///
+    /// Segments which aren't valid UTF-8 once decoded make the path malformed, as do
+    /// segments containing a slash below the path of a mount, since a mounted app only
+    /// sees a path. Unless passed through, encoded `.` and `..` segments, alone or
+    /// between encoded slashes, are traversals rather than being resolved.
+    pub fn decode(
+        mode: PathNormalization,
+        raw_path: &[u8],
+        is_mount: impl Fn(&[&str]) -> bool,
+    ) -> Result<Self, PathError> {
+        let raw_components: Vec<&[u8]> = raw_path.split(|&b| b == b'/').collect();
+        let components = raw_components
+            .iter()
+            .map(|component| percent_decode(component).decode_utf8())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| PathError::Malformed)?;
+        let components: Vec<&str> = components.iter().map(|c| c.as_ref()).collect();
+
+        let resolved = if mode == PathNormalization::PassThrough {
+            let segments: Vec<&str> = components
+                .iter()
+                .copied()
+                .filter(|c| !c.is_empty())
+                .collect();
+            Resolved {
+                trailing_slash: !segments.is_empty() && components.last() == Some(&""),
+                segments,
+                changed: false,
+            }
+        } else {
+            for (&raw, &component) in raw_components.iter().zip(&components) {
+                if component.contains('\0') {
+                    return Err(PathError::Malformed);
+                }
+                let encoded_dots = if component.contains('/') {
+                    component.split('/').any(is_dots)
+                } else {
+                    is_dots(component) && raw != component.as_bytes()
+                };
+                if encoded_dots {
+                    return Err(PathError::Traversal);
+                }
+            }
+            let resolved = resolve_dots(&components, &is_mount)?;
+            if mode == PathNormalization::Reject
+                && (resolved.changed || !raw_path.starts_with(b"/"))
+            {
+                return Err(PathError::Malformed);
+            }
+            resolved
+        };
+        if let Some(i) = resolved.segments.iter().position(|s| s.contains('/')) {
+            if (0..=i).any(|len| is_mount(&resolved.segments[..len])) {
+                return Err(PathError::Malformed);
+            }
+        }
+        Ok(Self {
+            path: resolved.join(),
+            segments: resolved.segments.iter().map(|&s| String::from(s)).collect(),
+            trailing_slash: resolved.trailing_slash,
+        })
+    }
+
+    /// Whether any of the segments contained an encoded slash
+    pub fn has_slash_in_segment(&self) -> bool {
+        self.segments.iter().any(|segment| segment.contains('/'))
+    }
+}
+
+/// The segments of a path, with its `.` and `..` segments resolved
+struct Resolved<'c> {
+    segments: Vec<&'c str>,
+    trailing_slash: bool,
+    /// If anything was resolved, or repeated slashes collapsed
+    changed: bool,
+}
+
+impl Resolved<'_> {
+    fn join(&self) -> String {
+        let mut path = String::new();
+        for segment in &self.segments {
+            path.push('/');
+            path.push_str(segment);
+        }
+        if path.is_empty() || self.trailing_slash {
+            path.push('/');
+        }
+        path
+    }
+}
+
+/// Resolve the `.` and `..` segments in the `/`-separated `components` of a path
+fn resolve_dots<'c>(
+    components: &[&'c str],
+    is_mount: impl Fn(&[&str]) -> bool,
+) -> Result<Resolved<'c>, PathError> {
+    let mut segments: Vec<&str> = Vec::with_capacity(components.len());
+    let mut changed = false;
+    for (i, &component) in components.iter().enumerate() {
+        match component {
+            // Leading and trailing slashes are fine, repeated ones aren't
+            "" => changed |= i != 0 && i != components.len() - 1,
+            "." => changed = true,
+            ".." => {
+                changed = true;
+                if segments.is_empty() || is_mount(&segments) {
+                    return Err(PathError::Traversal);
+                }
+                segments.pop();
+            }
+            segment => segments.push(segment),
+        }
+    }
+    // `/a/b/..` is `/a/`, like `/a/b/.` and `/a/b/` are `/a/b/`
+    let trailing_slash =
+        !segments.is_empty() && matches!(components.last(), Some(&("" | "." | "..")));
+    Ok(Resolved {
+        segments,
+        trailing_slash,
+        changed,
+    })
+}
+
+/// Whether `segment` is `.` or `..`
+fn is_dots(segment: &str) -> bool {
+    matches!(segment, "." | "..")
+}
+
+fn has_encoded_slash(raw_path: &[u8]) -> bool {
+    raw_path
+        .windows(3)
+        .any(|w| w[0] == b'%' && w[1] == b'2' && w[2].eq_ignore_ascii_case(&b'f'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn is_static(segments: &[&str]) -> bool {
+        segments == ["static"]
+    }
+
+    fn normalize(mode: PathNormalization, path: &str) -> Result<Cow<'_, str>, PathError> {
+        normalize_path(mode, path, None, is_static)
+    }
+
+    fn decode(mode: PathNormalization, raw_path: &[u8]) -> Result<RawPath, PathError> {
+        RawPath::decode(mode, raw_path, is_static)
+    }
+
+    #[test]
+    fn resolves_dot_segments() {
+        use PathNormalization::Normalize;
+        assert_eq!(normalize(Normalize, "/a/./b/../c").unwrap(), "/a/c");
+        assert_eq!(normalize(Normalize, "/a//b/").unwrap(), "/a/b/");
+        assert_eq!(normalize(Normalize, "/a/b/..").unwrap(), "/a/");
+        assert_eq!(normalize(Normalize, "/static/x/..").unwrap(), "/static/");
+        assert!(matches!(
+            normalize(Normalize, "/a/b").unwrap(),
+            Cow::Borrowed("/a/b")
+        ));
+        assert_eq!(
+            normalize(PathNormalization::PassThrough, "/a/../b").unwrap(),
+            "/a/../b"
+        );
+    }
+
+    #[test]
+    fn refuses_traversal() {
+        use PathNormalization::Normalize;
+        assert_eq!(normalize(Normalize, "/.."), Err(PathError::Traversal));
+        assert_eq!(normalize(Normalize, "/a/../.."), Err(PathError::Traversal));
+        // Out of a mount
+        assert_eq!(
+            normalize(Normalize, "/static/.."),
+            Err(PathError::Traversal)
+        );
+        assert_eq!(
+            normalize(Normalize, "/static/../secret"),
+            Err(PathError::Traversal)
+        );
+    }
+
+    #[test]
+    fn refuses_malformed_paths() {
+        use PathNormalization::Normalize;
+        assert_eq!(normalize(Normalize, "/a\0b"), Err(PathError::Malformed));
+        for raw_path in [&b"/a%2Fb"[..], b"/a%2fb"] {
+            assert_eq!(
+                normalize_path(Normalize, "/a/b", Some(raw_path), is_static),
+                Err(PathError::Malformed)
+            );
+        }
+        assert!(normalize_path(Normalize, "/a b", Some(b"/a%20b"), is_static).is_ok());
+    }
+
+    #[test]
+    fn reject_mode() {
+        use PathNormalization::Reject;
+        assert_eq!(normalize(Reject, "/a/b/").unwrap(), "/a/b/");
+        for path in ["/a//b", "/a/./b", "/a/../b", "a/b"] {
+            assert_eq!(
+                normalize(Reject, path),
+                Err(PathError::Malformed),
+                "{:?}",
+                path
+            );
+        }
+        assert_eq!(normalize(Reject, "/.."), Err(PathError::Traversal));
+    }
+
+    #[test]
+    fn decodes_raw_paths() {
+        use PathNormalization::*;
+        let raw = decode(Normalize, b"/files/a%2Fb/x/../c%20d/").unwrap();
+        assert_eq!(raw.segments, ["files", "a/b", "c d"]);
+        assert!(raw.trailing_slash);
+        assert!(raw.has_slash_in_segment());
+
+        let raw = decode(PassThrough, b"/a//b/../").unwrap();
+        assert_eq!(raw.segments, ["a", "b", ".."]);
+        assert_eq!(raw.path, "/a/b/../");
+
+        for mode in [PassThrough, Normalize, Reject] {
+            assert_eq!(decode(mode, b"/a%FF").unwrap_err(), PathError::Malformed);
+        }
+        assert_eq!(decode(Reject, b"/a//b").unwrap_err(), PathError::Malformed);
+        assert_eq!(
+            decode(Normalize, b"/a%00").unwrap_err(),
+            PathError::Malformed
+        );
+    }
+
+    #[test]
+    fn refuses_traversal_through_encoded_slashes() {
+        use PathNormalization::*;
+        for mode in [Normalize, Reject] {
+            for raw_path in [
+                &b"/static/..%2F..%2Fetc%2Fpasswd"[..],
+                b"/files/..%2F..%2Fetc",
+                b"/files/a%2F.%2Fb",
+                b"/files/%2E%2E",
+                b"/files/%2e",
+            ] {
+                assert_eq!(
+                    decode(mode, raw_path).unwrap_err(),
+                    PathError::Traversal,
+                    "{:?}",
+                    String::from_utf8_lossy(raw_path)
+                );
+            }
+        }
+        // Slashes can't be passed on to a mounted app as part of a segment
+        for mode in [PassThrough, Normalize, Reject] {
+            assert_eq!(
+                decode(mode, b"/static/a%2Fb").unwrap_err(),
+                PathError::Malformed
+            );
+        }
+        assert!(decode(Normalize, b"/static/a/b").is_ok());
+    }
+}
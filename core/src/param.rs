@@ -0,0 +1,445 @@
+//! Path parameter types, and parsing of the values a parameter of each can take
+//!
+//! The parsers accept the same strings as the Python types' constructors, within the
+//! ranges those types can hold, so a route only matches values its handler can be
+//! given. The exceptions are surrounding whitespace and non-ASCII digits, which
+//! `int`, `float` and `Decimal` accept but these don't.
+
+use std::borrow::Cow;
+
+/// A type a path parameter can be declared with (`{name:type}`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParamType {
+    Str,
+    Int,
+    Float,
+    Uuid,
+    Decimal,
+    Date,
+    DateTime,
+    Time,
+    TimeDelta,
+    Path,
+}
+
+impl ParamType {
+    /// The type called `name`, if there is one
+    ///
+    /// Anything else after the `:` of a parameter is a regex it must match.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "str" => Some(Self::Str),
+            "int" => Some(Self::Int),
+            "float" => Some(Self::Float),
+            "uuid" => Some(Self::Uuid),
+            "decimal" => Some(Self::Decimal),
+            "date" => Some(Self::Date),
+            "datetime" => Some(Self::DateTime),
+            "time" => Some(Self::Time),
+            "timedelta" => Some(Self::TimeDelta),
+            "path" => Some(Self::Path),
+            _ => None,
+        }
+    }
+
+    /// Whether `value` is valid for a parameter of this type
+    pub fn accepts(self, value: &str) -> bool {
+        match self {
+            Self::Str | Self::Path => true,
+            Self::Int => is_int(value),
+            Self::Float => parse_float(value).is_some(),
+            Self::Uuid => parse_uuid(value).is_some(),
+            Self::Decimal => is_decimal(value),
+            Self::Date => parse_date(value.as_bytes()).is_some(),
+            Self::DateTime => parse_datetime(value.as_bytes()).is_some(),
+            Self::Time => parse_time(value.as_bytes()).is_some(),
+            Self::TimeDelta => parse_duration(value).and_then(split_duration).is_some(),
+        }
+    }
+}
+
+/// `s` without the underscores grouping its digits, like `1_000`, or `None` if any
+/// underscore isn't between two digits
+pub fn remove_underscores(s: &str) -> Option<Cow<'_, str>> {
+    if !s.contains('_') {
+        return Some(Cow::Borrowed(s));
+    }
+    let bytes = s.as_bytes();
+    let grouped = bytes.iter().enumerate().all(|(i, &b)| {
+        b != b'_'
+            || (i > 0
+                && bytes[i - 1].is_ascii_digit()
+                && bytes.get(i + 1).is_some_and(u8::is_ascii_digit))
+    });
+    grouped.then(|| Cow::Owned(s.replace('_', "")))
+}
+
+/// Whether `s` is an optionally signed run of digits, which may be grouped with
+/// underscores
+pub fn is_int(s: &str) -> bool {
+    let digits = s.strip_prefix(['+', '-']).unwrap_or(s);
+    remove_underscores(digits)
+        .is_some_and(|digits| !digits.is_empty() && digits.bytes().all(|b| b.is_ascii_digit()))
+}
+
+/// Parse a float the way `float` does, digits grouped with underscores included
+pub fn parse_float(s: &str) -> Option<f64> {
+    remove_underscores(s)?.parse().ok()
+}
+
+/// Whether `s` is a number `decimal.Decimal` can parse
+pub fn is_decimal(s: &str) -> bool {
+    let s = match remove_underscores(s) {
+        Some(s) => s,
+        None => return false,
+    };
+    let s = s.strip_prefix(['+', '-']).unwrap_or(&s);
+    let special = ["inf", "infinity", "nan", "snan"];
+    if special.iter().any(|name| s.eq_ignore_ascii_case(name)) {
+        return true;
+    }
+    let (mantissa, exponent) = match s.find(['e', 'E']) {
+        Some(i) => (&s[..i], Some(&s[i + 1..])),
+        None => (s, None),
+    };
+    let (int_part, frac_part) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+    let all_digits = |part: &str| part.bytes().all(|b| b.is_ascii_digit());
+    let mantissa_ok = !(int_part.is_empty() && frac_part.is_empty())
+        && all_digits(int_part)
+        && all_digits(frac_part);
+    mantissa_ok && exponent.is_none_or(is_int)
+}
+
+/// Parse a uuid in any of the forms accepted by `uuid.UUID(hex)`
+pub fn parse_uuid(s: &str) -> Option<u128> {
+    let s = s.strip_prefix("urn:uuid:").unwrap_or(s);
+    let s = s.trim_matches(|c| c == '{' || c == '}');
+    let mut digits = 0;
+    let mut int = 0u128;
+    for c in s.chars().filter(|&c| c != '-') {
+        int = (int << 4) | u128::from(c.to_digit(16)?);
+        digits += 1;
+        if digits > 32 {
+            return None;
+        }
+    }
+    (digits == 32).then_some(int)
+}
+
+fn parse_digits<T: From<u8> + std::ops::Mul<Output = T> + std::ops::Add<Output = T>>(
+    s: &[u8],
+) -> Option<T> {
+    if s.is_empty() {
+        return None;
+    }
+    s.iter().try_fold(T::from(0), |acc, &b| {
+        b.is_ascii_digit()
+            .then(|| acc * T::from(10) + T::from(b - b'0'))
+    })
+}
+
+/// Parse an ISO 8601 `YYYY-MM-DD` date
+pub fn parse_date(s: &[u8]) -> Option<(i32, u8, u8)> {
+    let (year, month, day) = match s {
+        [y @ .., b'-', m1, m2, b'-', d1, d2] if y.len() == 4 => (
+            i32::from(parse_digits::<u16>(y)?),
+            parse_digits::<u8>(&[*m1, *m2])?,
+            parse_digits::<u8>(&[*d1, *d2])?,
+        ),
+        _ => return None,
+    };
+    let leap = year % 4 == 0 && (year % 100 != 0 || year % 400 == 0);
+    let days_in_month = match month {
+        2 if leap => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        1..=12 => 31,
+        _ => return None,
+    };
+    (year >= 1 && (1..=days_in_month).contains(&day)).then_some((year, month, day))
+}
+
+pub struct DateTime {
+    pub date: (i32, u8, u8),
+    pub time: Time,
+}
+
+/// Parse an ISO 8601 datetime: `YYYY-MM-DD[(T| )HH:MM[:SS[.ffffff]][Z|(+|-)HH:MM]]`
+pub fn parse_datetime(s: &[u8]) -> Option<DateTime> {
+    if s.len() <= 10 {
+        return Some(DateTime {
+            date: parse_date(s)?,
+            time: Time {
+                hms: (0, 0, 0, 0),
+                offset: None,
+            },
+        });
+    }
+    let (date, sep, rest) = (&s[..10], s[10], &s[11..]);
+    if sep != b'T' && sep != b' ' {
+        return None;
+    }
+    Some(DateTime {
+        date: parse_date(date)?,
+        time: parse_time(rest)?,
+    })
+}
+
+/// A time of day
+pub struct Time {
+    pub hms: (u8, u8, u8, u32),
+    /// Offset from UTC in seconds, if any
+    pub offset: Option<i32>,
+}
+
+/// Parse an ISO 8601 time: `HH:MM[:SS[.ffffff]][Z|(+|-)HH:MM]`
+pub fn parse_time(s: &[u8]) -> Option<Time> {
+    let (time, offset) = match s.iter().position(|&b| matches!(b, b'Z' | b'+' | b'-')) {
+        Some(i) => (&s[..i], Some(&s[i..])),
+        None => (s, None),
+    };
+    let offset = match offset {
+        None => None,
+        Some(b"Z") => Some(0),
+        Some([sign, h1, h2, b':', m1, m2]) => {
+            let hours: i32 = parse_digits(&[*h1, *h2])?;
+            let minutes: i32 = parse_digits(&[*m1, *m2])?;
+            if hours >= 24 || minutes >= 60 {
+                return None;
+            }
+            let offset = hours * 3600 + minutes * 60;
+            Some(if *sign == b'-' { -offset } else { offset })
+        }
+        Some(_) => return None,
+    };
+
+    let (hms, fraction) = match time.iter().position(|&b| b == b'.') {
+        Some(i) => (&time[..i], Some(&time[i + 1..])),
+        None => (time, None),
+    };
+    let (hour, minute, second) = match hms {
+        [h1, h2, b':', m1, m2] => (parse_digits(&[*h1, *h2])?, parse_digits(&[*m1, *m2])?, 0),
+        [h1, h2, b':', m1, m2, b':', s1, s2] => (
+            parse_digits(&[*h1, *h2])?,
+            parse_digits(&[*m1, *m2])?,
+            parse_digits(&[*s1, *s2])?,
+        ),
+        _ => return None,
+    };
+    if hour >= 24 || minute >= 60 || second >= 60 {
+        return None;
+    }
+    let microsecond = match fraction {
+        Some(fraction) if fraction.len() <= 6 && hms.len() == 8 => {
+            let scale = 10u32.pow(6 - fraction.len() as u32);
+            parse_digits::<u32>(fraction)? * scale
+        }
+        Some(_) => return None,
+        None => 0,
+    };
+    Some(Time {
+        hms: (hour, minute, second, microsecond),
+        offset,
+    })
+}
+
+const SECOND: i128 = 1_000_000;
+const MINUTE: i128 = 60 * SECOND;
+const HOUR: i128 = 60 * MINUTE;
+const DAY: i128 = 24 * HOUR;
+
+/// Parse a duration into microseconds: a number of seconds, a `timedelta`'s string,
+/// `[D day[s], ][-]H:MM[:SS[.ffffff]]`, or an ISO 8601 duration,
+/// `[-]P[nW][nD][T[nH][nM][nS]]`
+pub fn parse_duration(s: &str) -> Option<i128> {
+    if let Ok(seconds) = s.parse::<f64>() {
+        // `as` saturates, leaving values too big to be `None` once split into days
+        return seconds
+            .is_finite()
+            .then(|| (seconds * SECOND as f64).round() as i128);
+    }
+    let (negative, unsigned) = match s.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, s.strip_prefix('+').unwrap_or(s)),
+    };
+    let micros = match (unsigned.strip_prefix('P'), s.split_once(", ")) {
+        (Some(iso), _) => parse_iso_duration(iso)?,
+        // Only the days are signed, as in `-1 day, 23:59:59`
+        (None, Some((days, clock))) => {
+            let days = days
+                .strip_suffix(" days")
+                .or_else(|| days.strip_suffix(" day"))?;
+            let days: i128 = match days.strip_prefix('-') {
+                Some(digits) => -parse_number(digits)?,
+                None => parse_number(days)?,
+            };
+            return Some(days * DAY + parse_clock(clock)?);
+        }
+        (None, None) => parse_clock(unsigned)?,
+    };
+    Some(if negative { -micros } else { micros })
+}
+
+/// Parse `H:MM[:SS[.ffffff]]` into microseconds
+fn parse_clock(s: &str) -> Option<i128> {
+    let (hms, fraction) = match s.split_once('.') {
+        Some((hms, fraction)) => (hms, Some(fraction)),
+        None => (s, None),
+    };
+    let two_digits = |part: &str| (part.len() == 2).then(|| parse_number(part))?;
+    let mut parts = hms.split(':');
+    let hours = parse_number(parts.next()?)?;
+    let minutes = two_digits(parts.next()?)?;
+    let seconds = match parts.next() {
+        Some(part) => two_digits(part)?,
+        None if fraction.is_none() => 0,
+        None => return None,
+    };
+    if parts.next().is_some() || minutes >= 60 || seconds >= 60 {
+        return None;
+    }
+    let microseconds = match fraction {
+        Some(fraction) if fraction.len() <= 6 => {
+            parse_number(fraction)? * 10i128.pow(6 - fraction.len() as u32)
+        }
+        Some(_) => return None,
+        None => 0,
+    };
+    Some(hours * HOUR + minutes * MINUTE + seconds * SECOND + microseconds)
+}
+
+/// Parse the rest of an ISO 8601 duration after its `P` into microseconds
+fn parse_iso_duration(s: &str) -> Option<i128> {
+    let (date, time) = match s.split_once('T') {
+        Some((date, time)) if !time.is_empty() => (date, time),
+        Some(_) => return None,
+        None if !s.is_empty() => (s, ""),
+        None => return None,
+    };
+    let mut micros = 0.0;
+    for (part, units) in [
+        (date, &[('W', 7 * DAY), ('D', DAY)][..]),
+        (time, &[('H', HOUR), ('M', MINUTE), ('S', SECOND)][..]),
+    ] {
+        // Each unit at most once, in order
+        let mut units = units.iter();
+        let mut rest = part;
+        while !rest.is_empty() {
+            let end = rest.find(|c: char| c.is_ascii_alphabetic())?;
+            let number = &rest[..end];
+            let designator = rest[end..].chars().next()?;
+            let &(_, unit) = units.find(|(d, _)| *d == designator)?;
+            let valid =
+                !number.is_empty() && number.bytes().all(|b| b.is_ascii_digit() || b == b'.');
+            let value: f64 = number.parse().ok().filter(|_| valid)?;
+            micros += value * unit as f64;
+            rest = &rest[end + 1..];
+        }
+    }
+    Some(micros.round() as i128)
+}
+
+/// Split a duration in microseconds into the days, seconds and microseconds of a
+/// `timedelta`, if it's within the range one can hold
+pub fn split_duration(micros: i128) -> Option<(i32, i32, i32)> {
+    let days = micros.div_euclid(DAY);
+    if days.abs() > 999_999_999 {
+        return None;
+    }
+    let rest = micros.rem_euclid(DAY);
+    Some((days as i32, (rest / SECOND) as i32, (rest % SECOND) as i32))
+}
+
+/// Parse a non-empty run of at most 18 digits
+fn parse_number(s: &str) -> Option<i128> {
+    (s.len() <= 18).then(|| parse_digits(s.as_bytes()))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dates() {
+        assert_eq!(parse_date(b"2024-02-29"), Some((2024, 2, 29)));
+        for invalid in [
+            "2023-02-29",
+            "1900-02-29",
+            "2024-04-31",
+            "2024-13-01",
+            "2024-00-10",
+            "0000-01-01",
+            "2024-1-01",
+            "24-01-01",
+        ] {
+            assert_eq!(parse_date(invalid.as_bytes()), None, "{:?}", invalid);
+        }
+    }
+
+    #[test]
+    fn times() {
+        let hms = |s: &str| parse_time(s.as_bytes()).map(|time| (time.hms, time.offset));
+        assert_eq!(hms("12:30"), Some(((12, 30, 0, 0), None)));
+        assert_eq!(hms("12:30:05.25"), Some(((12, 30, 5, 250_000), None)));
+        assert_eq!(hms("12:30:05Z"), Some(((12, 30, 5, 0), Some(0))));
+        assert_eq!(hms("12:30-01:30"), Some(((12, 30, 0, 0), Some(-5400))));
+        assert_eq!(hms("12"), None);
+        assert_eq!(hms("12:3"), None);
+        assert_eq!(hms("12:30:05.1234567"), None);
+        assert_eq!(hms("24:00"), None);
+        assert_eq!(hms("12:30:60"), None);
+    }
+
+    #[test]
+    fn durations() {
+        assert_eq!(parse_duration("10"), Some(10 * SECOND));
+        assert_eq!(parse_duration("-1.5"), Some(-1_500_000));
+        assert_eq!(parse_duration("0:00:10"), Some(10 * SECOND));
+        assert_eq!(parse_duration("1:02"), Some(HOUR + 2 * MINUTE));
+        assert_eq!(parse_duration("-2:00:00.5"), Some(-2 * HOUR - 500_000));
+        assert_eq!(parse_duration("2 days, 1:00:00"), Some(2 * DAY + HOUR));
+        assert_eq!(parse_duration("-1 day, 23:59:59"), Some(-SECOND));
+        assert_eq!(parse_duration("P1W2D"), Some(9 * DAY));
+        assert_eq!(parse_duration("PT1H30M"), Some(HOUR + 30 * MINUTE));
+        assert_eq!(parse_duration("-P1DT0.5S"), Some(-DAY - 500_000));
+        for invalid in [
+            "", "P", "P1DT", "PT1D", "P1H", "PT1M1H", "1:60:00", "abc", "inf",
+        ] {
+            assert_eq!(parse_duration(invalid), None, "{:?}", invalid);
+        }
+    }
+
+    #[test]
+    fn duration_ranges() {
+        assert_eq!(split_duration(-SECOND), Some((-1, 86399, 0)));
+        assert_eq!(split_duration(DAY + 1), Some((1, 0, 1)));
+        assert_eq!(split_duration(1_000_000_000 * DAY), None);
+        assert_eq!(split_duration(i128::from(i32::MIN) * DAY), None);
+        assert!(!ParamType::TimeDelta.accepts("-185542587187200"));
+    }
+
+    #[test]
+    fn accepts() {
+        assert!(ParamType::Int.accepts("-12"));
+        assert!(!ParamType::Int.accepts("12.5"));
+        assert!(ParamType::Float.accepts("12.5"));
+        // Digits can be grouped with underscores, as in Python
+        assert!(ParamType::Int.accepts("1_000"));
+        assert!(ParamType::Float.accepts("1_0.2_5e1_0"));
+        assert!(ParamType::Decimal.accepts("-1_000.5"));
+        for invalid in ["_1", "1_", "1__0", "+_1"] {
+            assert!(!ParamType::Int.accepts(invalid), "{:?}", invalid);
+        }
+        for invalid in ["1_.5", "1._5", "1_e5", "inf_"] {
+            assert!(!ParamType::Float.accepts(invalid), "{:?}", invalid);
+            assert!(!ParamType::Decimal.accepts(invalid), "{:?}", invalid);
+        }
+        assert!(ParamType::Uuid.accepts("12345678-1234-5678-1234-567812345678"));
+        assert!(!ParamType::Uuid.accepts("1234"));
+        assert!(ParamType::Decimal.accepts("1.5e3"));
+        assert!(ParamType::DateTime.accepts("2024-01-02T03:04:05Z"));
+        assert!(!ParamType::DateTime.accepts("2024-01-02X03:04"));
+        assert!(ParamType::TimeDelta.accepts("P1D"));
+        assert!(ParamType::Str.accepts("anything"));
+    }
+}
@@ -0,0 +1,214 @@
+use crate::param::ParamType;
+use crate::Error;
+use percent_encoding::{AsciiSet, CONTROLS};
+use regex::Regex;
+use std::borrow::Cow;
+
+/// Characters which must be escaped in a path segment
+///
+/// Backslashes are too, since browsers treat them like slashes.
+pub const SEGMENT: &AsciiSet = &CONTROLS
+    .add(b' ')
+    .add(b'"')
+    .add(b'#')
+    .add(b'%')
+    .add(b'<')
+    .add(b'>')
+    .add(b'?')
+    .add(b'`')
+    .add(b'{')
+    .add(b'}')
+    .add(b'/')
+    .add(b'\\');
+
+/// A single segment of a route's path
+#[derive(Debug)]
+pub(crate) enum Segment<'a> {
+    Literal(&'a str),
+    /// A segment which is just a `{name}` or `{name:type}` parameter
+    Placeholder,
+    /// A `{name:path}` parameter, capturing all remaining segments
+    CatchAll,
+    /// Anything else containing parameters, e.g. `{name:regex}` or `{name}.{ext}`
+    Pattern(SegmentPattern),
+}
+
+impl<'a> Segment<'a> {
+    /// Parse a segment of a route's path
+    ///
+    /// Only `{...}` for which `is_param` is true (given what's inside the braces) are
+    /// treated as parameters, anything else is literal text.
+    pub(crate) fn parse(s: &'a str, is_param: &dyn Fn(&str) -> bool) -> Result<Self, Error> {
+        let pieces = split_pieces(s, is_param);
+        match pieces.as_slice() {
+            [] => Ok(Self::Literal(s)),
+            [(0, end, full)] if *end == s.len() => match param_spec(full) {
+                Some("path") => Ok(Self::CatchAll),
+                Some(spec) if ParamType::from_name(spec).is_none() => {
+                    SegmentPattern::new(s, &pieces).map(Self::Pattern)
+                }
+                _ => Ok(Self::Placeholder),
+            },
+            _ => SegmentPattern::new(s, &pieces).map(Self::Pattern),
+        }
+    }
+}
+
+/// A segment mixing literal text with parameters, or constraining a parameter with a regex
+///
+/// Segments with the same pieces (ignoring parameter names) are equal, so routes using
+/// them share the same node, and conflicting parameters are caught like any others.
+#[derive(Debug)]
+pub(crate) struct SegmentPattern {
+    pieces: Vec<Piece>,
+    regex: Regex,
+    /// Index of the capture group for each parameter, in order
+    groups: Vec<usize>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum Piece {
+    Literal(String),
+    Param { constraint: Option<String> },
+}
+
+impl SegmentPattern {
+    fn new(s: &str, params: &[(usize, usize, &str)]) -> Result<Self, Error> {
+        let mut pieces = Vec::with_capacity(params.len() * 2 + 1);
+        let mut last_end = 0;
+        for &(start, end, full) in params {
+            if start > last_end {
+                pieces.push(Piece::Literal(String::from(&s[last_end..start])));
+            } else if start != 0 {
+                return Err(Error(format!(
+                    "Path segment {:?} has parameters without anything separating them",
+                    s
+                )));
+            }
+            let constraint = match param_spec(full) {
+                Some("path") => {
+                    return Err(Error(format!(
+                        "Path segment {:?} can't contain a parameter of type `path`",
+                        s
+                    )))
+                }
+                Some(spec) if ParamType::from_name(spec).is_none() => Some(String::from(spec)),
+                _ => None,
+            };
+            pieces.push(Piece::Param { constraint });
+            last_end = end;
+        }
+        if last_end < s.len() {
+            pieces.push(Piece::Literal(String::from(&s[last_end..])));
+        }
+
+        let constraints = pieces.iter().filter_map(|piece| match piece {
+            Piece::Literal(_) => None,
+            Piece::Param { constraint } => Some(constraint.as_deref().unwrap_or(".+")),
+        });
+        let regex = Regex::new(&params_regex(s, params, constraints))
+            .map_err(|e| Error(format!("Invalid path parameter pattern in {:?}: {}", s, e)))?;
+        let groups = param_groups(&regex, params.len());
+        Ok(Self {
+            pieces,
+            regex,
+            groups,
+        })
+    }
+
+    /// Match `component` against this pattern, pushing the value of each parameter onto
+    /// `params` if it matches
+    pub(crate) fn match_into<'p>(
+        &self,
+        component: &'p str,
+        params: &mut Vec<Cow<'p, str>>,
+    ) -> bool {
+        let captures = match self.regex.captures(component) {
+            Some(captures) => captures,
+            None => return false,
+        };
+        params.extend(
+            self.groups
+                .iter()
+                .map(|&i| Cow::Borrowed(captures.get(i).map_or("", |m| m.as_str()))),
+        );
+        true
+    }
+}
+
+impl PartialEq for SegmentPattern {
+    fn eq(&self, other: &Self) -> bool {
+        self.pieces == other.pieces
+    }
+}
+
+/// Build a regex matching the whole of `s`, with its `params` (as found by
+/// `split_pieces`) matching each of `constraints` in turn, and the rest literally
+///
+/// The parameters are captured by groups named `p0`, `p1`, etc, see `param_groups`.
+pub(crate) fn params_regex<'c>(
+    s: &str,
+    params: &[(usize, usize, &str)],
+    constraints: impl IntoIterator<Item = &'c str>,
+) -> String {
+    let mut regex = String::from("^");
+    let mut last_end = 0;
+    for (i, (&(start, end, _), constraint)) in params.iter().zip(constraints).enumerate() {
+        regex.push_str(&regex::escape(&s[last_end..start]));
+        // Named, so any groups in the constraint don't throw off our indexes
+        regex.push_str(&format!("(?P<p{}>{})", i, constraint));
+        last_end = end;
+    }
+    regex.push_str(&regex::escape(&s[last_end..]));
+    regex.push('$');
+    regex
+}
+
+/// The index of the capture group of each of the first `count` parameters of a
+/// regex built by `params_regex`
+pub(crate) fn param_groups(regex: &Regex, count: usize) -> Vec<usize> {
+    (0..count)
+        .map(|i| {
+            let name = format!("p{}", i);
+            regex
+                .capture_names()
+                .position(|group| group == Some(name.as_str()))
+                .unwrap()
+        })
+        .collect()
+}
+
+/// The part of a parameter's full name after the `:`, if any
+pub fn param_spec(full: &str) -> Option<&str> {
+    full.split_once(':').map(|(_, spec)| spec)
+}
+
+/// Find the parameters in `s` (the `{...}` for which `is_param` is true), returning
+/// their start and end offsets (including the braces) and full names
+pub fn split_pieces(s: &str, is_param: impl Fn(&str) -> bool) -> Vec<(usize, usize, &str)> {
+    let mut params = Vec::new();
+    let mut start = None;
+    let mut depth = 0;
+    for (i, c) in s.char_indices() {
+        match c {
+            '{' => {
+                if depth == 0 {
+                    start = Some(i);
+                }
+                depth += 1;
+            }
+            '}' if depth > 0 => {
+                depth -= 1;
+                if depth == 0 {
+                    let start = start.take().unwrap();
+                    let full = &s[start + 1..i];
+                    if is_param(full) {
+                        params.push((start, i + 1, full));
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    params
+}
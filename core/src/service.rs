@@ -0,0 +1,458 @@
+//! Routing `http::Request`s with a [`Router`], as a `tower::Service`
+//!
+//! Each route has a service for each method it handles, and a mount usually has one
+//! for any method, from [`Methods::any`]. They must all be of the same type, so
+//! services of different types need boxing, e.g. with `tower::util::BoxCloneService`.
+//!
+//! ```
+//! use http::{Method, Request, Response, StatusCode};
+//! use starlite_router_core::service::{PathParams, RouterService};
+//! use starlite_router_core::Router;
+//! use std::convert::Infallible;
+//! use tower::{service_fn, ServiceExt};
+//!
+//! # #[tokio::main(flavor = "current_thread")]
+//! # async fn main() {
+//! let user = service_fn(|request: Request<()>| async move {
+//!     let params = request.extensions().get::<PathParams>().unwrap();
+//!     Ok::<_, Infallible>(Response::new(format!("user {}", params.get("id").unwrap())))
+//! });
+//! let mut router = Router::default();
+//! router.route("/users/{id}", Method::GET, user).unwrap();
+//! let service = RouterService::new(router);
+//!
+//! let request = Request::get("/users/42").body(()).unwrap();
+//! let response = service.clone().oneshot(request).await.unwrap();
+//! assert_eq!(response.into_body(), "user 42");
+//!
+//! let request = Request::post("/users/42").body(()).unwrap();
+//! let response = service.clone().oneshot(request).await.unwrap();
+//! assert_eq!(response.status(), StatusCode::METHOD_NOT_ALLOWED);
+//! assert_eq!(response.headers()["allow"], "GET");
+//!
+//! let request = Request::get("/posts").body(()).unwrap();
+//! let response = service.oneshot(request).await.unwrap();
+//! assert_eq!(response.status(), StatusCode::NOT_FOUND);
+//! # }
+//! ```
+
+use crate::{Error, Router};
+use http::header::{HeaderValue, ALLOW};
+use http::uri::{PathAndQuery, Uri};
+use http::{Method, Request, Response, StatusCode};
+use percent_encoding::percent_decode_str;
+use pin_project_lite::pin_project;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use tower::util::Oneshot;
+use tower::{Service, ServiceExt};
+
+/// The services of a route, by the method of the requests they handle, with any
+/// service for the other methods
+#[derive(Debug, Clone)]
+pub struct Methods<S> {
+    services: Vec<(Method, S)>,
+    any: Option<S>,
+}
+
+impl<S> Methods<S> {
+    /// `service` for requests with any method, like an app to mount
+    pub fn any(service: S) -> Self {
+        Self {
+            services: Vec::new(),
+            any: Some(service),
+        }
+    }
+
+    /// The methods there's a service for, sorted
+    pub fn allowed(&self) -> Vec<&str> {
+        let mut methods: Vec<&str> = self
+            .services
+            .iter()
+            .map(|(method, _)| method.as_str())
+            .collect();
+        methods.sort_unstable();
+        methods
+    }
+
+    fn get(&self, method: &Method) -> Option<&S> {
+        self.services
+            .iter()
+            .find(|(m, _)| m == method)
+            .map(|(_, service)| service)
+            .or(self.any.as_ref())
+    }
+}
+
+impl<S> From<Vec<(Method, S)>> for Methods<S> {
+    fn from(services: Vec<(Method, S)>) -> Self {
+        Self {
+            services,
+            any: None,
+        }
+    }
+}
+
+impl<S> Router<Methods<S>> {
+    /// Route requests to `path` with `method` to `service`, instead of any service
+    /// they were routed to before
+    pub fn route(&mut self, path: &str, method: Method, service: S) -> Result<&mut Self, Error> {
+        let methods = &mut self
+            .get_or_insert_with(path, |_| true, false, || Methods::from(Vec::new()))?
+            .value
+            .services;
+        match methods.iter_mut().find(|(m, _)| *m == method) {
+            Some((_, existing)) => *existing = service,
+            None => methods.push((method, service)),
+        }
+        Ok(self)
+    }
+}
+
+/// The values of the parameters of the route a request matched, added to its
+/// extensions
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PathParams(Vec<(String, String)>);
+
+impl PathParams {
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.0
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// The names and values of the parameters, in the order they appear in the path
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.0
+            .iter()
+            .map(|(name, value)| (name.as_str(), value.as_str()))
+    }
+}
+
+/// The prefix of the mounts a request was routed through, added to its extensions
+///
+/// Like an ASGI scope's `root_path`, the prefix is removed from the front of the
+/// request's URI, and appended to any root path the request already had.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RootPath(String);
+
+impl RootPath {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+/// A `tower::Service` dispatching requests to the service for their route and method
+///
+/// Requests which match no route get an empty 404 response, and those with a method
+/// their route has no service for get a 405 response, with an `Allow` header.
+/// Mounted services get requests with the mount's prefix moved from the URI's path to
+/// their [`RootPath`]. No trailing-slash redirects are made: with `TrailingSlash::Redirect`, paths match as
+/// they would with `TrailingSlash::Strict`.
+#[derive(Debug)]
+pub struct RouterService<S> {
+    router: Arc<Router<Methods<S>>>,
+}
+
+impl<S> RouterService<S> {
+    pub fn new(router: Router<Methods<S>>) -> Self {
+        Self {
+            router: Arc::new(router),
+        }
+    }
+}
+
+impl<S> Clone for RouterService<S> {
+    fn clone(&self) -> Self {
+        Self {
+            router: Arc::clone(&self.router),
+        }
+    }
+}
+
+impl<S, B, ResBody> Service<Request<B>> for RouterService<S>
+where
+    S: Service<Request<B>, Response = Response<ResBody>> + Clone,
+    ResBody: Default,
+{
+    type Response = Response<ResBody>;
+    type Error = S::Error;
+    type Future = ResponseFuture<S, B>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        // Routes' services are only called once they're ready, in `ResponseFuture`
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, mut request: Request<B>) -> Self::Future {
+        // Paths are matched once decoded, like an ASGI scope's `path`
+        let path = match percent_decode_str(request.uri().path()).decode_utf8() {
+            Ok(path) => path.into_owned(),
+            Err(_) => return ResponseFuture::ready(empty_response(StatusCode::NOT_FOUND)),
+        };
+        let found = match self.router.find(&path) {
+            Some(found) => found,
+            None => return ResponseFuture::ready(empty_response(StatusCode::NOT_FOUND)),
+        };
+        let methods = &found.route.value;
+        let service = match methods.get(request.method()) {
+            Some(service) => service.clone(),
+            None => {
+                let mut response = empty_response(StatusCode::METHOD_NOT_ALLOWED);
+                if let Ok(allow) = HeaderValue::from_str(&methods.allowed().join(", ")) {
+                    response.headers_mut().insert(ALLOW, allow);
+                }
+                return ResponseFuture::ready(response);
+            }
+        };
+        let params = found
+            .route
+            .param_names()
+            .iter()
+            .zip(&found.params)
+            .map(|(name, value)| (name.clone(), String::from(value.as_ref())))
+            .collect();
+        request.extensions_mut().insert(PathParams(params));
+        if let Some((prefix, rest)) = found.route.split_mount(&path) {
+            strip_mount_prefix(&mut request, prefix, rest);
+        }
+        ResponseFuture {
+            kind: Kind::Routed {
+                future: service.oneshot(request),
+            },
+        }
+    }
+}
+
+/// Move the `prefix` of a mount from the front of the path of `request` to its
+/// [`RootPath`], leaving the `rest` of the path
+fn strip_mount_prefix<B>(request: &mut Request<B>, prefix: &str, rest: &str) {
+    let uri = request.uri();
+    let raw = uri.path();
+    // Split the path before it was decoded, so the rest keeps its original encoding
+    let split = raw
+        .match_indices('/')
+        .map(|(i, _)| i)
+        .chain([raw.len()])
+        .find(|&i| {
+            percent_decode_str(&raw[..i])
+                .decode_utf8()
+                .is_ok_and(|p| p == prefix)
+        });
+    let raw_rest = match split.map(|i| &raw[i..]) {
+        Some("") => "/",
+        Some(raw_rest) => raw_rest,
+        None => rest,
+    };
+    let path_and_query = match uri.query() {
+        Some(query) => format!("{}?{}", raw_rest, query),
+        None => String::from(raw_rest),
+    };
+    let mut parts = uri.clone().into_parts();
+    parts.path_and_query = PathAndQuery::try_from(path_and_query).ok();
+    if let Ok(uri) = Uri::from_parts(parts) {
+        *request.uri_mut() = uri;
+    }
+    let root_path = request.extensions_mut().get_or_insert_default::<RootPath>();
+    root_path.0.push_str(prefix);
+}
+
+fn empty_response<B: Default>(status: StatusCode) -> Response<B> {
+    let mut response = Response::new(B::default());
+    *response.status_mut() = status;
+    response
+}
+
+pin_project! {
+    /// The response of a [`RouterService`]
+    pub struct ResponseFuture<S, B>
+    where
+        S: Service<Request<B>>,
+    {
+        #[pin]
+        kind: Kind<S, B>,
+    }
+}
+
+pin_project! {
+    #[project = KindProj]
+    enum Kind<S, B>
+    where
+        S: Service<Request<B>>,
+    {
+        /// Waiting for the route's service
+        Routed {
+            #[pin]
+            future: Oneshot<S, Request<B>>,
+        },
+        /// Responding without a service, for requests which aren't routed
+        Ready {
+            response: Option<S::Response>,
+        },
+    }
+}
+
+impl<S, B> ResponseFuture<S, B>
+where
+    S: Service<Request<B>>,
+{
+    fn ready(response: S::Response) -> Self {
+        Self {
+            kind: Kind::Ready {
+                response: Some(response),
+            },
+        }
+    }
+}
+
+impl<S, B> Future for ResponseFuture<S, B>
+where
+    S: Service<Request<B>>,
+{
+    type Output = Result<S::Response, S::Error>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        match self.project().kind.project() {
+            KindProj::Routed { future } => future.poll(cx),
+            KindProj::Ready { response } => {
+                Poll::Ready(Ok(response.take().expect("polled after completion")))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TrailingSlash;
+    use std::convert::Infallible;
+    use tower::service_fn;
+    use tower::util::BoxCloneService;
+
+    type Handler = BoxCloneService<Request<()>, Response<String>, Infallible>;
+
+    /// A service responding with `name` and the request's path parameters
+    fn handler(name: &'static str) -> Handler {
+        BoxCloneService::new(service_fn(move |request: Request<()>| async move {
+            let params = request.extensions().get::<PathParams>().unwrap();
+            let params: Vec<String> = params
+                .iter()
+                .map(|(name, value)| format!("{}={}", name, value))
+                .collect();
+            Ok(Response::new(format!("{} {}", name, params.join(","))))
+        }))
+    }
+
+    async fn call(
+        router: &RouterService<Handler>,
+        method: Method,
+        path: &str,
+    ) -> (StatusCode, String) {
+        let request = Request::builder()
+            .method(method)
+            .uri(path)
+            .body(())
+            .unwrap();
+        let response = router.clone().oneshot(request).await.unwrap();
+        (response.status(), response.into_body())
+    }
+
+    #[tokio::test]
+    async fn typed_params() {
+        let mut router = Router::default();
+        router
+            .route("/users/{id:int}", Method::GET, handler("user"))
+            .unwrap()
+            .route("/files/{name}.{ext}", Method::GET, handler("file"))
+            .unwrap();
+        let router = RouterService::new(router);
+        assert_eq!(
+            call(&router, Method::GET, "/users/42").await,
+            (StatusCode::OK, String::from("user id=42"))
+        );
+        assert_eq!(
+            call(&router, Method::GET, "/users/abc").await.0,
+            StatusCode::NOT_FOUND
+        );
+        assert_eq!(
+            call(&router, Method::GET, "/files/a%20b.txt").await,
+            (StatusCode::OK, String::from("file name=a b,ext=txt"))
+        );
+    }
+
+    #[tokio::test]
+    async fn mounts() {
+        // Responds with the path and root path it sees
+        let mounted = BoxCloneService::new(service_fn(|request: Request<()>| async move {
+            let root_path = request.extensions().get::<RootPath>().unwrap();
+            let body = format!("{} {}", root_path.as_str(), request.uri());
+            Ok::<_, Infallible>(Response::new(body))
+        }));
+        let mut router = Router::default();
+        router
+            .route("/api/users", Method::GET, handler("users"))
+            .unwrap();
+        router.mount("/api", Methods::any(mounted)).unwrap();
+        let router = RouterService::new(router);
+        assert_eq!(call(&router, Method::GET, "/api/users").await.1, "users ");
+        assert_eq!(call(&router, Method::POST, "/api/x").await.1, "/api /x");
+        // Routes still only handle their own methods
+        assert_eq!(
+            call(&router, Method::POST, "/api/users").await.0,
+            StatusCode::METHOD_NOT_ALLOWED
+        );
+        assert_eq!(
+            call(&router, Method::GET, "/api/other/a%2Fb?q=1").await.1,
+            "/api /other/a%2Fb?q=1"
+        );
+        assert_eq!(call(&router, Method::GET, "/api").await.1, "/api /");
+        assert_eq!(call(&router, Method::GET, "/%61pi/x").await.1, "/api /x");
+        assert_eq!(
+            call(&router, Method::GET, "/other").await.0,
+            StatusCode::NOT_FOUND
+        );
+    }
+
+    #[tokio::test]
+    async fn trailing_slash_modes() {
+        for (mode, slash_status) in [
+            (TrailingSlash::Lenient, StatusCode::OK),
+            (TrailingSlash::Strict, StatusCode::NOT_FOUND),
+            // Redirecting is left to the caller
+            (TrailingSlash::Redirect(307), StatusCode::NOT_FOUND),
+        ] {
+            let mut router = Router::new(mode);
+            router.route("/a", Method::GET, handler("a")).unwrap();
+            let router = RouterService::new(router);
+            assert_eq!(call(&router, Method::GET, "/a").await.0, StatusCode::OK);
+            assert_eq!(
+                call(&router, Method::GET, "/a/").await.0,
+                slash_status,
+                "{:?}",
+                mode
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn method_not_allowed() {
+        let mut router = Router::default();
+        router
+            .route("/items", Method::POST, handler("create"))
+            .unwrap()
+            .route("/items", Method::GET, handler("list"))
+            .unwrap()
+            .route("/items", Method::DELETE, handler("clear"))
+            .unwrap();
+        let router = RouterService::new(router);
+        assert_eq!(call(&router, Method::GET, "/items").await.1, "list ");
+        assert_eq!(call(&router, Method::DELETE, "/items").await.1, "clear ");
+        let request = Request::put("/items").body(()).unwrap();
+        let response = router.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::METHOD_NOT_ALLOWED);
+        assert_eq!(response.headers()[ALLOW], "DELETE, GET, POST");
+    }
+}
@@ -0,0 +1,151 @@
+//! API versions, and finding the version a request asks for
+
+use percent_encoding::percent_decode;
+use std::fmt;
+
+/// The version of an API, like `2` or `1.1`
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Version(Vec<u32>);
+
+impl Version {
+    /// Parse a version, with or without a leading `v`
+    pub fn parse(s: &str) -> Option<Self> {
+        let s = s.trim();
+        let s = s.strip_prefix(['v', 'V']).unwrap_or(s);
+        s.split('.')
+            .map(|part| {
+                if part.is_empty() || !part.bytes().all(|b| b.is_ascii_digit()) {
+                    return None;
+                }
+                part.parse().ok()
+            })
+            .collect::<Option<_>>()
+            .map(Self)
+    }
+
+    /// Whether a route of this version can serve a request for `requested`
+    ///
+    /// The major versions must be the same, and this can't be newer than what was
+    /// requested, unless only the major version was.
+    pub fn serves(&self, requested: &Version) -> bool {
+        self.0[0] == requested.0[0] && (requested.0.len() == 1 || *self <= *requested)
+    }
+}
+
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, part) in self.0.iter().enumerate() {
+            if i != 0 {
+                f.write_str(".")?;
+            }
+            write!(f, "{}", part)?;
+        }
+        Ok(())
+    }
+}
+
+/// Split a `/v{version}` prefix off `path`
+pub fn split_version_prefix(path: &str) -> Option<(&str, &str)> {
+    let end = path
+        .strip_prefix('/')?
+        .find('/')
+        .map_or(path.len(), |i| i + 1);
+    let (prefix, rest) = path.split_at(end);
+    let version = prefix.strip_prefix("/v")?;
+    Version::parse(version)?;
+    Some((prefix, rest))
+}
+
+/// The value of the parameter `name` in a query string
+pub fn query_value(query_string: &[u8], name: &str) -> Option<String> {
+    let decode = |s: &[u8]| -> String {
+        let s: Vec<u8> = s
+            .iter()
+            .map(|&b| if b == b'+' { b' ' } else { b })
+            .collect();
+        percent_decode(&s).decode_utf8_lossy().into_owned()
+    };
+    query_string
+        .split(|&b| b == b'&')
+        .filter_map(|pair| {
+            let mut parts = pair.splitn(2, |&b| b == b'=');
+            Some((parts.next()?, parts.next().unwrap_or(b"")))
+        })
+        .find(|(key, _)| decode(key) == name)
+        .map(|(_, value)| decode(value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn version(s: &str) -> Version {
+        Version::parse(s).unwrap()
+    }
+
+    #[test]
+    fn parse() {
+        assert_eq!(version("v1.2"), Version(vec![1, 2]));
+        assert_eq!(version(" V3 "), Version(vec![3]));
+        assert_eq!(version("1.2").to_string(), "1.2");
+        for invalid in ["", "v", "1.", ".1", "1beta", "1.x", "-1"] {
+            assert_eq!(Version::parse(invalid), None, "{:?}", invalid);
+        }
+    }
+
+    #[test]
+    fn major_only_request_gets_any_minor() {
+        assert!(version("1").serves(&version("1")));
+        assert!(version("1.0").serves(&version("1")));
+        assert!(version("1.9").serves(&version("1")));
+        assert!(!version("2.0").serves(&version("1")));
+        assert!(!version("1.9").serves(&version("2")));
+    }
+
+    #[test]
+    fn minor_request_caps_the_version() {
+        assert!(version("1").serves(&version("1.2")));
+        assert!(version("1.1").serves(&version("1.2")));
+        assert!(version("1.2").serves(&version("1.2")));
+        assert!(!version("1.3").serves(&version("1.2")));
+        assert!(!version("1.2.1").serves(&version("1.2")));
+        assert!(version("2.0").serves(&version("2.1")));
+        assert!(!version("0.9").serves(&version("1.2")));
+    }
+
+    #[test]
+    fn version_prefixes() {
+        assert_eq!(split_version_prefix("/v1/users"), Some(("/v1", "/users")));
+        assert_eq!(split_version_prefix("/v1.2"), Some(("/v1.2", "")));
+        assert_eq!(split_version_prefix("/v2/"), Some(("/v2", "/")));
+        assert_eq!(split_version_prefix("/v1beta/users"), None);
+        assert_eq!(split_version_prefix("/v/users"), None);
+        assert_eq!(split_version_prefix("/users/v1"), None);
+        assert_eq!(split_version_prefix("/"), None);
+        assert_eq!(split_version_prefix(""), None);
+        assert_eq!(split_version_prefix("é/v1"), None);
+    }
+
+    #[test]
+    fn query_values() {
+        assert_eq!(
+            query_value(b"a=1&version=2", "version").as_deref(),
+            Some("2")
+        );
+        assert_eq!(
+            query_value(b"version=1+beta", "version").as_deref(),
+            Some("1 beta")
+        );
+        assert_eq!(
+            query_value(b"v%65rsion=1%2E2", "version").as_deref(),
+            Some("1.2")
+        );
+        assert_eq!(query_value(b"version&x=1", "version").as_deref(), Some(""));
+        assert_eq!(
+            query_value(b"version=1&version=2", "version").as_deref(),
+            Some("1")
+        );
+        assert_eq!(query_value(b"versions=1", "version"), None);
+        assert_eq!(query_value(b"", "version"), None);
+    }
+}
@@ -0,0 +1,363 @@
+use pyo3::exceptions::PyStopIteration;
+use pyo3::prelude::*;
+use pyo3::pyclass::IterNextOutput;
+use pyo3::types::{PyBytes, PyDict, PyList, PyType};
+use std::collections::VecDeque;
+
+/// A minimal ASGI app, sending a fixed http response
+///
+/// Websockets are closed instead, as they can't be sent a response.
+#[pyclass(module = "starlite_router")]
+#[derive(Debug)]
+pub(crate) struct Responder {
+    status: u16,
+    headers: Vec<(&'static str, String)>,
+    body: Vec<u8>,
+}
+
+impl Responder {
+    /// Respond to an `OPTIONS` request, with an `Allow` header listing `methods`
+    pub(crate) fn options(methods: &[&str]) -> Self {
+        Self {
+            status: 204,
+            headers: vec![("allow", methods.join(", "))],
+            body: Vec::new(),
+        }
+    }
+
+    /// Redirect to `location`
+    pub(crate) fn redirect(status: u16, location: String) -> Self {
+        Self {
+            status,
+            headers: vec![("location", location)],
+            body: Vec::new(),
+        }
+    }
+
+    /// Respond to a request which matches no route
+    pub(crate) fn not_found() -> Self {
+        Self::plain_text(404, Vec::new(), "Not Found")
+    }
+
+    /// Respond to a request whose path is malformed
+    pub(crate) fn bad_request() -> Self {
+        Self::plain_text(400, Vec::new(), "Bad Request")
+    }
+
+    /// Respond to a request none of its route's handlers can produce an acceptable
+    /// response for
+    pub(crate) fn not_acceptable() -> Self {
+        Self::plain_text(406, Vec::new(), "Not Acceptable")
+    }
+
+    /// Respond to a request for a method its route has no handler for, with an `Allow`
+    /// header listing `methods`
+    pub(crate) fn method_not_allowed(methods: &[&str]) -> Self {
+        Self::plain_text(
+            405,
+            vec![("allow", methods.join(", "))],
+            "Method Not Allowed",
+        )
+    }
+
+    fn plain_text(status: u16, mut headers: Vec<(&'static str, String)>, body: &str) -> Self {
+        headers.push(("content-type", String::from("text/plain; charset=utf-8")));
+        headers.push(("content-length", body.len().to_string()));
+        Self {
+            status,
+            headers,
+            body: body.as_bytes().to_vec(),
+        }
+    }
+}
+
+#[pymethods]
+impl Responder {
+    fn __call__(
+        &self,
+        py: Python<'_>,
+        scope: &PyAny,
+        _receive: &PyAny,
+        send: &PyAny,
+    ) -> PyResult<Coroutine> {
+        let scope_type: Option<&str> = match scope.get_item(pyo3::intern!(py, "type")) {
+            Ok(scope_type) => scope_type.extract()?,
+            Err(_) => None,
+        };
+        if scope_type == Some("websocket") {
+            let close = PyDict::new(py);
+            close.set_item("type", "websocket.close")?;
+            close.set_item("code", 1000)?;
+            return Ok(Coroutine::new(SendMessages::new(send, [close.into()])));
+        }
+        let headers = PyList::empty(py);
+        for (name, value) in &self.headers {
+            headers.append((
+                PyBytes::new(py, name.as_bytes()),
+                PyBytes::new(py, value.as_bytes()),
+            ))?;
+        }
+        let start = PyDict::new(py);
+        start.set_item("type", "http.response.start")?;
+        start.set_item("status", self.status)?;
+        start.set_item("headers", headers)?;
+        let body = PyDict::new(py);
+        body.set_item("type", "http.response.body")?;
+        body.set_item("body", PyBytes::new(py, &self.body))?;
+        Ok(Coroutine::new(SendMessages::new(
+            send,
+            [start.into(), body.into()],
+        )))
+    }
+
+    fn __repr__(&self) -> String {
+        format!("{:?}", self)
+    }
+}
+
+/// How an awaitable was resumed, passed on to the one it's awaiting
+pub(crate) enum Resume {
+    Next,
+    Send(PyObject),
+    Throw(PyErr),
+}
+
+/// What an awaitable did when resumed
+enum Resumed {
+    Yield(PyObject),
+    /// It completed, with this result
+    Return(PyObject),
+}
+
+/// Resume `iter`, the iterator of an awaitable being awaited, the way its awaiter was
+///
+/// Event loops other than asyncio, like trio, resume coroutines with values, so these
+/// are sent on rather than only calling `__next__`.
+fn resume(py: Python<'_>, iter: &PyAny, how: Resume) -> PyResult<Resumed> {
+    let result = match how {
+        Resume::Send(value) if !value.is_none(py) => iter.call_method1("send", (value,)),
+        Resume::Next | Resume::Send(_) => iter.call_method0("__next__"),
+        Resume::Throw(err) => match iter.getattr(pyo3::intern!(py, "throw")) {
+            Ok(throw) => throw.call1((err.value(py),)),
+            Err(_) => Err(err),
+        },
+    };
+    match result {
+        Ok(value) => Ok(Resumed::Yield(value.into())),
+        Err(e) if e.is_instance_of::<PyStopIteration>(py) => {
+            let value = e.value(py).getattr(pyo3::intern!(py, "value"))?;
+            Ok(Resumed::Return(value.into()))
+        }
+        Err(e) => Err(e),
+    }
+}
+
+/// The exception passed to a coroutine's `throw`, as `throw(type[, value])` or
+/// `throw(exception)`
+fn thrown(ty: &PyAny, value: Option<&PyAny>) -> PyResult<PyErr> {
+    let value = value.filter(|value| !value.is_none());
+    match (ty.downcast::<PyType>(), value) {
+        (Ok(ty), Some(value)) if value.is_instance(ty)? => Ok(PyErr::from_value(value)),
+        (Ok(ty), Some(value)) => Ok(PyErr::from_value(ty.call1((value,))?)),
+        _ => Ok(PyErr::from_value(ty)),
+    }
+}
+
+/// The state of an awaitable implemented in Rust, run by a [`Coroutine`]
+pub(crate) trait Steps: Send {
+    /// Carry on until the next value to yield, or the result, resumed `how` the
+    /// awaitable's awaiter resumed it
+    fn resume(
+        &mut self,
+        py: Python<'_>,
+        how: Resume,
+    ) -> PyResult<IterNextOutput<PyObject, PyObject>>;
+}
+
+/// An awaitable running `steps`, which is its own iterator
+#[pyclass(module = "starlite_router")]
+pub(crate) struct Coroutine {
+    steps: Box<dyn Steps>,
+}
+
+impl Coroutine {
+    pub(crate) fn new(steps: impl Steps + 'static) -> Self {
+        Self {
+            steps: Box::new(steps),
+        }
+    }
+}
+
+#[pymethods]
+impl Coroutine {
+    fn __await__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    fn __next__(&mut self, py: Python<'_>) -> PyResult<IterNextOutput<PyObject, PyObject>> {
+        self.steps.resume(py, Resume::Next)
+    }
+
+    fn send(
+        &mut self,
+        py: Python<'_>,
+        value: PyObject,
+    ) -> PyResult<IterNextOutput<PyObject, PyObject>> {
+        self.steps.resume(py, Resume::Send(value))
+    }
+
+    #[args(value = "None", traceback = "None")]
+    fn throw(
+        &mut self,
+        ty: &PyAny,
+        value: Option<&PyAny>,
+        traceback: Option<&PyAny>,
+    ) -> PyResult<IterNextOutput<PyObject, PyObject>> {
+        // An exception's traceback is already attached to it
+        let _ = traceback;
+        self.steps
+            .resume(ty.py(), Resume::Throw(thrown(ty, value)?))
+    }
+}
+
+/// Sends each of `messages` in turn, awaiting each `send` call
+pub(crate) struct SendMessages {
+    send: PyObject,
+    messages: VecDeque<PyObject>,
+    /// The iterator of the `send` call currently being awaited
+    current: Option<PyObject>,
+}
+
+impl SendMessages {
+    pub(crate) fn new(send: &PyAny, messages: impl IntoIterator<Item = PyObject>) -> Self {
+        Self {
+            send: send.into(),
+            messages: messages.into_iter().collect(),
+            current: None,
+        }
+    }
+}
+
+impl Steps for SendMessages {
+    fn resume(
+        &mut self,
+        py: Python<'_>,
+        mut how: Resume,
+    ) -> PyResult<IterNextOutput<PyObject, PyObject>> {
+        loop {
+            if let Some(current) = &self.current {
+                match resume(py, current.as_ref(py), how) {
+                    Ok(Resumed::Yield(value)) => return Ok(IterNextOutput::Yield(value)),
+                    Ok(Resumed::Return(_)) => self.current = None,
+                    Err(e) => {
+                        self.current = None;
+                        self.messages.clear();
+                        return Err(e);
+                    }
+                }
+            } else if let Resume::Throw(err) = how {
+                self.messages.clear();
+                return Err(err);
+            }
+            how = Resume::Next;
+            let message = match self.messages.pop_front() {
+                Some(message) => message,
+                None => return Ok(IterNextOutput::Return(py.None())),
+            };
+            let awaitable = self.send.call1(py, (message,))?;
+            self.current = Some(awaitable.call_method0(py, "__await__")?);
+        }
+    }
+}
+
+/// Runs the lifespan protocol for an app with nothing to do on startup or shutdown,
+/// completing each event as it's received
+pub(crate) struct Lifespan {
+    receive: PyObject,
+    send: PyObject,
+    /// The iterator of the `receive` or `send` call currently being awaited
+    current: Option<(PyObject, Step)>,
+    done: bool,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Step {
+    Receive,
+    /// Sending the completion of an event, and whether it's the last one
+    Complete {
+        last: bool,
+    },
+}
+
+impl Lifespan {
+    pub(crate) fn new(receive: &PyAny, send: &PyAny) -> Self {
+        Self {
+            receive: receive.into(),
+            send: send.into(),
+            current: None,
+            done: false,
+        }
+    }
+
+    /// Start awaiting `awaitable`
+    fn start(&mut self, py: Python<'_>, awaitable: PyObject, step: Step) -> PyResult<()> {
+        let iter = awaitable.call_method0(py, "__await__")?;
+        self.current = Some((iter, step));
+        Ok(())
+    }
+}
+
+impl Steps for Lifespan {
+    fn resume(
+        &mut self,
+        py: Python<'_>,
+        mut how: Resume,
+    ) -> PyResult<IterNextOutput<PyObject, PyObject>> {
+        loop {
+            let (iter, step) = match (&self.current, how) {
+                (Some((iter, step)), resumed) => {
+                    how = resumed;
+                    (iter, *step)
+                }
+                (None, Resume::Throw(err)) => {
+                    self.done = true;
+                    return Err(err);
+                }
+                (None, _) if self.done => return Ok(IterNextOutput::Return(py.None())),
+                (None, _) => {
+                    let awaitable = self.receive.call0(py)?;
+                    self.start(py, awaitable, Step::Receive)?;
+                    how = Resume::Next;
+                    continue;
+                }
+            };
+            let result = match resume(py, iter.as_ref(py), how) {
+                Ok(Resumed::Yield(value)) => return Ok(IterNextOutput::Yield(value)),
+                Ok(Resumed::Return(result)) => result.into_ref(py),
+                Err(e) => {
+                    self.current = None;
+                    self.done = true;
+                    return Err(e);
+                }
+            };
+            how = Resume::Next;
+            self.current = None;
+            match step {
+                Step::Receive => {
+                    let message_type: &str =
+                        result.get_item(pyo3::intern!(py, "type"))?.extract()?;
+                    let (completed, last) = match message_type {
+                        "lifespan.startup" => ("lifespan.startup.complete", false),
+                        "lifespan.shutdown" => ("lifespan.shutdown.complete", true),
+                        _ => continue,
+                    };
+                    let message = PyDict::new(py);
+                    message.set_item("type", completed)?;
+                    let awaitable = self.send.call1(py, (message,))?;
+                    self.start(py, awaitable, Step::Complete { last })?;
+                }
+                Step::Complete { last } => self.done = last,
+            }
+        }
+    }
+}
@@ -0,0 +1,258 @@
+use pyo3::exceptions::{PyException, PyImportError, PyValueError};
+use pyo3::once_cell::GILOnceCell;
+use pyo3::prelude::*;
+use pyo3::types::{IntoPyDict, PyDict, PyTuple, PyType};
+
+// Raised when no other exceptions are given, and starlite isn't installed
+pyo3::create_exception!(
+    starlite_router,
+    ImproperlyConfiguredException,
+    PyException,
+    "A route can't be added"
+);
+pyo3::create_exception!(
+    starlite_router,
+    NotFoundException,
+    PyException,
+    "No route matches a request"
+);
+pyo3::create_exception!(
+    starlite_router,
+    MethodNotAllowedException,
+    PyException,
+    "A request's route has no handler for its method"
+);
+pyo3::create_exception!(
+    starlite_router,
+    NoRouteMatchFoundException,
+    PyException,
+    "No route has the name, or parameters, a path was asked for with"
+);
+pyo3::create_exception!(
+    starlite_router,
+    ValidationException,
+    PyException,
+    "A request's path is malformed"
+);
+pyo3::create_exception!(
+    starlite_router,
+    NotAcceptableException,
+    PyException,
+    "No handler can produce a response a request accepts"
+);
+pyo3::create_exception!(
+    starlite_router,
+    UnsupportedScopeTypeException,
+    PyException,
+    "A scope is of a type there's no handler for"
+);
+pyo3::create_exception!(
+    starlite_router,
+    VersionNotAvailableException,
+    NotFoundException,
+    "No route serves the API version a request asked for"
+);
+
+/// The exceptions a `RouteMap` raises
+///
+/// Each is called to create the exception it raises, with a message or nothing, so can
+/// be an exception class or any other callable returning an exception.
+#[derive(Debug, Clone)]
+pub(crate) struct Exceptions {
+    improperly_configured: PyObject,
+    not_found: PyObject,
+    /// Raised with the methods which are allowed set as its `allowed_methods` attribute
+    method_not_allowed: PyObject,
+    no_route_match_found: PyObject,
+    validation: PyObject,
+    not_acceptable: PyObject,
+    version_not_available: PyObject,
+    unsupported_scope_type: PyObject,
+}
+
+impl Exceptions {
+    /// Get the exceptions for a `RouteMap`, with any in `overrides` replacing the
+    /// defaults
+    ///
+    /// The defaults are starlite's exceptions if it's installed, otherwise our own.
+    pub(crate) fn new(py: Python<'_>, overrides: Option<&PyDict>) -> PyResult<Self> {
+        let mut exceptions = match Self::starlite(py)? {
+            Some(starlite) => starlite.clone(),
+            None => Self::builtin(py),
+        };
+        for (key, exception) in overrides.into_iter().flatten() {
+            let field = match key.extract()? {
+                "improperly_configured" => &mut exceptions.improperly_configured,
+                "not_found" => &mut exceptions.not_found,
+                "method_not_allowed" => &mut exceptions.method_not_allowed,
+                "no_route_match_found" => &mut exceptions.no_route_match_found,
+                "validation" => &mut exceptions.validation,
+                "not_acceptable" => &mut exceptions.not_acceptable,
+                "version_not_available" => &mut exceptions.version_not_available,
+                "unsupported_scope_type" => &mut exceptions.unsupported_scope_type,
+                key => {
+                    return Err(PyValueError::new_err(format!(
+                        "Unknown exception {:?}",
+                        key
+                    )))
+                }
+            };
+            *field = exception.into();
+        }
+        Ok(exceptions)
+    }
+
+    fn builtin(py: Python<'_>) -> Self {
+        Self {
+            improperly_configured: py.get_type::<ImproperlyConfiguredException>().into(),
+            not_found: py.get_type::<NotFoundException>().into(),
+            method_not_allowed: py.get_type::<MethodNotAllowedException>().into(),
+            no_route_match_found: py.get_type::<NoRouteMatchFoundException>().into(),
+            validation: py.get_type::<ValidationException>().into(),
+            not_acceptable: py.get_type::<NotAcceptableException>().into(),
+            version_not_available: py.get_type::<VersionNotAvailableException>().into(),
+            unsupported_scope_type: py.get_type::<UnsupportedScopeTypeException>().into(),
+        }
+    }
+
+    /// Starlite's exceptions, unless it isn't installed
+    ///
+    /// Versions which aren't available are still a `VersionNotAvailableException`, but
+    /// also a subclass of starlite's `NotFoundException`.
+    fn starlite(py: Python<'_>) -> PyResult<Option<&Self>> {
+        static STARLITE: GILOnceCell<Option<Exceptions>> = GILOnceCell::new();
+        if let Some(starlite) = STARLITE.get(py) {
+            return Ok(starlite.as_ref());
+        }
+        let starlite = Self::import_starlite(py)?;
+        Ok(STARLITE.get_or_init(py, || starlite).as_ref())
+    }
+
+    fn import_starlite(py: Python<'_>) -> PyResult<Option<Self>> {
+        let module = match py.import("starlite.exceptions") {
+            Ok(module) => module,
+            Err(e) if e.is_instance_of::<PyImportError>(py) => return Ok(None),
+            Err(e) => return Err(e),
+        };
+        let not_found = module.getattr("NotFoundException")?;
+        let bases = PyTuple::new(
+            py,
+            [py.get_type::<VersionNotAvailableException>(), not_found],
+        );
+        let namespace = [("__module__", "starlite_router")].into_py_dict(py);
+        let version_not_available =
+            py.get_type::<PyType>()
+                .call1(("VersionNotAvailableException", bases, namespace))?;
+        let kwargs = [("status_code", 406)].into_py_dict(py);
+        let not_acceptable = py
+            .import("functools")?
+            .getattr("partial")?
+            .call((module.getattr("HTTPException")?,), Some(kwargs))?;
+        Ok(Some(Self {
+            improperly_configured: module.getattr("ImproperlyConfiguredException")?.into(),
+            not_found: not_found.into(),
+            method_not_allowed: module.getattr("MethodNotAllowedException")?.into(),
+            no_route_match_found: module.getattr("NoRouteMatchFoundException")?.into(),
+            validation: module.getattr("ValidationException")?.into(),
+            not_acceptable: not_acceptable.into(),
+            version_not_available: version_not_available.into(),
+            unsupported_scope_type: py.get_type::<UnsupportedScopeTypeException>().into(),
+        }))
+    }
+
+    pub(crate) fn improperly_configured(&self, py: Python<'_>, message: String) -> PyErr {
+        raise(py, &self.improperly_configured, (message,))
+    }
+
+    /// The exception for a route the router can't add
+    pub(crate) fn route_error(&self, py: Python<'_>, err: starlite_router_core::Error) -> PyErr {
+        self.improperly_configured(py, err.to_string())
+    }
+
+    pub(crate) fn not_found(&self, py: Python<'_>) -> PyErr {
+        raise(py, &self.not_found, ())
+    }
+
+    pub(crate) fn method_not_allowed(&self, py: Python<'_>, allowed_methods: &[&str]) -> PyErr {
+        let err = raise(py, &self.method_not_allowed, ());
+        match err
+            .value(py)
+            .setattr(pyo3::intern!(py, "allowed_methods"), allowed_methods)
+        {
+            Ok(()) => err,
+            Err(e) => e,
+        }
+    }
+
+    pub(crate) fn no_route_match_found(&self, py: Python<'_>, message: String) -> PyErr {
+        raise(py, &self.no_route_match_found, (message,))
+    }
+
+    /// The exception for requests no handler can produce an acceptable response for
+    pub(crate) fn not_acceptable(&self, py: Python<'_>) -> PyErr {
+        raise(py, &self.not_acceptable, ())
+    }
+
+    pub(crate) fn version_not_available(&self, py: Python<'_>, requested: &str) -> PyErr {
+        let message = format!("API version {:?} is not available", requested);
+        raise(py, &self.version_not_available, (message,))
+    }
+
+    pub(crate) fn unsupported_scope_type(&self, py: Python<'_>, scope_type: &str) -> PyErr {
+        let message = format!("Unsupported scope type {:?}", scope_type);
+        raise(py, &self.unsupported_scope_type, (message,))
+    }
+
+    /// The exception for a path which is malformed, and can't be routed
+    pub(crate) fn malformed_path(&self, py: Python<'_>) -> PyErr {
+        raise(py, &self.validation, ("Malformed path",))
+    }
+}
+
+/// Create an exception by calling `exception` with `args`
+fn raise(py: Python<'_>, exception: &PyObject, args: impl IntoPy<Py<PyTuple>>) -> PyErr {
+    match exception.call1(py, args) {
+        Ok(err) => PyErr::from_value(err.into_ref(py)),
+        Err(e) => e,
+    }
+}
+
+/// Add our own exceptions to the module
+///
+/// If starlite is installed, `VersionNotAvailableException` is the subclass of its
+/// `NotFoundException` which is raised.
+pub(crate) fn add_to_module(py: Python<'_>, m: &PyModule) -> PyResult<()> {
+    m.add(
+        "ImproperlyConfiguredException",
+        py.get_type::<ImproperlyConfiguredException>(),
+    )?;
+    m.add("NotFoundException", py.get_type::<NotFoundException>())?;
+    m.add(
+        "MethodNotAllowedException",
+        py.get_type::<MethodNotAllowedException>(),
+    )?;
+    m.add(
+        "NoRouteMatchFoundException",
+        py.get_type::<NoRouteMatchFoundException>(),
+    )?;
+    m.add("ValidationException", py.get_type::<ValidationException>())?;
+    m.add(
+        "NotAcceptableException",
+        py.get_type::<NotAcceptableException>(),
+    )?;
+    m.add(
+        "UnsupportedScopeTypeException",
+        py.get_type::<UnsupportedScopeTypeException>(),
+    )?;
+    match Exceptions::starlite(py)? {
+        Some(starlite) => m.add(
+            "VersionNotAvailableException",
+            &starlite.version_not_available,
+        )?,
+        None => m.add(
+            "VersionNotAvailableException",
+            py.get_type::<VersionNotAvailableException>(),
+        )?,
+    }
+    Ok(())
+}
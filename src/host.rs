@@ -0,0 +1,18 @@
+use pyo3::prelude::*;
+use pyo3::types::PyMapping;
+
+/// The host a request was made to, from the `host` header in its scope
+pub(crate) fn scope_host(scope: &PyMapping) -> PyResult<Option<String>> {
+    let py = scope.py();
+    let headers = match scope.get_item(pyo3::intern!(py, "headers")) {
+        Ok(headers) => headers,
+        Err(_) => return Ok(None),
+    };
+    for header in headers.iter()? {
+        let (name, value): (&[u8], &[u8]) = header?.extract()?;
+        if name.eq_ignore_ascii_case(b"host") {
+            return Ok(Some(String::from_utf8_lossy(value).into_owned()));
+        }
+    }
+    Ok(None)
+}
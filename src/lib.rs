@@ -1,52 +1,353 @@
 use pyo3::prelude::*;
-use pyo3::types::{PyList, PyMapping, PySequence, PyType};
+use pyo3::types::{PyBytes, PyDict, PyList, PyMapping, PySequence};
+use pyo3::AsPyPointer;
 
 use ahash::AHashMap as HashMap;
 use ahash::AHashSet as HashSet;
-use pyo3::exceptions::PyTypeError;
+use pyo3::exceptions::{PyKeyError, PyTypeError, PyValueError};
+use std::borrow::Cow;
 use std::collections::HashMap as StdHashMap;
 
-type ASGIApp = PyAny;
+mod asgi;
+mod exceptions;
+mod host;
+mod negotiate;
+mod params;
+mod reverse;
+mod routes;
+mod version;
 
-mod exceptions {
-    pyo3::import_exception!(starlite.exceptions, ImproperlyConfiguredException);
-    pyo3::import_exception!(starlite.exceptions, MethodNotAllowedException);
-    pyo3::import_exception!(starlite.exceptions, NotFoundException);
-}
+use asgi::{Coroutine, Lifespan, Responder};
+use exceptions::Exceptions;
+use params::{ParamTypes, PathParam};
+use reverse::RouteTemplate;
+use routes::RouteTypes;
+use starlite_router_core::host::{HostParams, HostPattern};
+use starlite_router_core::negotiate::{HeaderPredicates, Rank};
+use starlite_router_core::normalize::{self, PathError, PathNormalization, RawPath};
+use starlite_router_core::version::Version;
+use starlite_router_core::{Match, Route, Router, TrailingSlash};
+use version::{Requested, Versioning};
+
+type ASGIApp = PyAny;
 
 #[pyclass]
 #[derive(Debug)]
 struct RouteMap {
-    app: StarliteApp,
+    /// The app to build handlers with, if any
+    app: Option<StarliteApp>,
     route_types: RouteTypes,
-    path_param_parser: Py<PyAny>,
-    param_routes: Node,
-    plain_routes: HashMap<String, Leaf>,
+    exceptions: Exceptions,
+    param_types: ParamTypes,
+    routes: Router<Leaf>,
+    /// Paths of routes, by the name of their handlers
+    names: HashMap<String, Vec<RouteTemplate>>,
+    options: Options,
+    /// Route maps for requests to other hosts, in the order they were added
+    hosts: Vec<(HostPattern, Py<RouteMap>)>,
+    /// Handlers for types of scope which aren't routed by path, like `lifespan`, by
+    /// type
+    scope_handlers: HashMap<String, Py<ASGIApp>>,
 }
 
-#[derive(Debug, Default)]
-struct Node {
-    children: HashMap<String, Node>,
-    placeholder_child: Option<Box<Node>>,
-    leaf: Option<Leaf>,
+/// Optional behaviours of a `RouteMap`, set as keyword arguments when creating it
+#[derive(Debug, Clone, Default)]
+struct Options {
+    /// Handle `HEAD` requests with the `GET` handler, if there's no `HEAD` handler
+    auto_head: bool,
+    /// Respond to `OPTIONS` requests with the allowed methods, if there's no
+    /// `OPTIONS` handler
+    auto_options: bool,
+    path_normalization: PathNormalization,
+    /// Match against the scope's `raw_path`, so path parameters can contain encoded
+    /// slashes
+    match_raw_path: bool,
+    /// Where the API version a request wants is taken from, if anywhere
+    versioning: Option<Versioning>,
+    /// The app `__call__` responds with when no route matches, instead of a plain 404
+    not_found: Option<Py<ASGIApp>>,
+    /// The app `__call__` responds with when a route has no handler for a request's
+    /// method, instead of a plain 405
+    method_not_allowed: Option<Py<ASGIApp>>,
+}
+
+/// The outcome of routing a scope
+enum Resolved {
+    Handler(Py<ASGIApp>),
+    NotFound,
+    /// The route has no handler for the request's method, only for these
+    MethodNotAllowed(Vec<String>),
+    /// The path is malformed
+    BadRequest,
+    /// The request asks for this API version, which isn't available
+    VersionNotAvailable(String),
+    /// None of the route's handlers can produce a response the request accepts
+    NotAcceptable,
+}
+
+impl From<PathError> for Resolved {
+    fn from(err: PathError) -> Self {
+        match err {
+            PathError::Malformed => Self::BadRequest,
+            PathError::Traversal => Self::NotFound,
+        }
+    }
+}
+
+/// Why none of a route's handlers can serve a request
+#[derive(Debug)]
+enum Unservable {
+    /// It asks for this API version, which none of them serve
+    VersionNotAvailable(String),
+    /// None of them can produce a response it accepts
+    NotAcceptable,
+}
+
+impl From<Unservable> for Resolved {
+    fn from(unservable: Unservable) -> Self {
+        match unservable {
+            Unservable::VersionNotAvailable(requested) => Self::VersionNotAvailable(requested),
+            Unservable::NotAcceptable => Self::NotAcceptable,
+        }
+    }
 }
 
+/// A handler for a scope, with the websocket subprotocol it was chosen for
+type Selected<'a> = (Py<ASGIApp>, Option<&'a str>);
+
+/// The handlers of a route
 #[derive(Debug)]
 struct Leaf {
     is_asgi: bool,
-    static_path: Option<String>,
     path_parameters: Py<PyAny>,
+    params: Vec<PathParam>,
     asgi_handlers: HashMap<HandlerType, Py<ASGIApp>>,
+    /// Names of the route handlers in `asgi_handlers`, for those which have one
+    handler_names: HashMap<HandlerType, String>,
+    /// Handlers which are only chosen for requests with matching headers, preferred
+    /// over those in `asgi_handlers`
+    candidates: HashMap<HandlerType, Vec<Candidate>>,
+}
+
+/// A handler which is only chosen for some requests: those with matching headers or
+/// subprotocols, or which want its API version
+#[derive(Debug)]
+struct Candidate {
+    predicates: HeaderPredicates,
+    version: Option<Version>,
+    handler: Py<ASGIApp>,
+    name: Option<String>,
+}
+
+/// What a request wants, which candidates are chosen by
+#[derive(Debug, Default)]
+struct Wants<'a> {
+    headers: Vec<(&'a [u8], &'a [u8])>,
+    /// The subprotocols a websocket asks for, most preferred first
+    subprotocols: Vec<String>,
+    version: Option<Version>,
+}
+
+/// A handler chosen for a request
+#[derive(Debug)]
+struct Chosen<'a> {
+    handler: &'a Py<ASGIApp>,
+    /// The websocket subprotocol the handler was chosen for
+    subprotocol: Option<&'a str>,
+}
+
+/// The candidate whose predicates match `wants` best, the first one on a tie
+fn best_candidate<'c>(
+    candidates: impl Iterator<Item = &'c Candidate>,
+    wants: &Wants<'_>,
+) -> Option<Chosen<'c>> {
+    let mut best: Option<(Rank<'c>, &Candidate)> = None;
+    for candidate in candidates {
+        if let Some(rank) = candidate
+            .predicates
+            .rank(&wants.headers, &wants.subprotocols)
+        {
+            if best.is_none_or(|(best_rank, _)| rank > best_rank) {
+                best = Some((rank, candidate));
+            }
+        }
+    }
+    best.map(|(rank, candidate)| Chosen {
+        handler: &candidate.handler,
+        subprotocol: rank.subprotocol(),
+    })
 }
 
 impl Leaf {
-    fn new(params: Py<PyAny>) -> Self {
-        Self {
-            path_parameters: params,
+    fn new(path_parameters: &PyAny, param_types: &ParamTypes) -> PyResult<Self> {
+        let params = path_parameters
+            .iter()?
+            .map(|definition| PathParam::from_definition(definition?, param_types))
+            .collect::<PyResult<_>>()?;
+        Ok(Self {
+            path_parameters: path_parameters.into(),
+            params,
             asgi_handlers: Default::default(),
+            handler_names: Default::default(),
+            candidates: Default::default(),
             is_asgi: false,
-            static_path: None,
+        })
+    }
+
+    fn is_empty(&self) -> bool {
+        self.asgi_handlers.is_empty() && self.candidates.is_empty()
+    }
+
+    /// Get the handler for a scope of type `scope_type`, using `method` for http scopes
+    ///
+    /// Only mounted apps handle scopes other than http and websocket ones.
+    fn handler(
+        &self,
+        scope_type: &str,
+        method: Option<&str>,
+        wants: &Wants<'_>,
+    ) -> Result<Option<Chosen<'_>>, Unservable> {
+        let handler_type = if self.is_asgi {
+            HandlerType::Asgi
+        } else {
+            match (scope_type, method) {
+                ("http", Some(method)) => HandlerType::from_http_method(method),
+                ("websocket", _) => HandlerType::Websocket,
+                _ => return Ok(None),
+            }
+        };
+        self.choose(&handler_type, wants)
+    }
+
+    /// Choose the handler of type `handler_type` for a request
+    ///
+    /// Candidates of the newest version which can serve the request, and has a
+    /// candidate whose predicates match it, are preferred, then those without a
+    /// version, picking the one whose predicates match the request's headers and
+    /// subprotocols best. Otherwise, the handler which isn't a
+    /// candidate is used.
+    /// If there isn't one, the request is for an unavailable version, or not
+    /// acceptable.
+    fn choose(
+        &self,
+        handler_type: &HandlerType,
+        wants: &Wants<'_>,
+    ) -> Result<Option<Chosen<'_>>, Unservable> {
+        let default = self.asgi_handlers.get(handler_type).map(|handler| Chosen {
+            handler,
+            subprotocol: None,
+        });
+        let candidates = match self.candidates.get(handler_type) {
+            Some(candidates) => candidates,
+            None => return Ok(default),
+        };
+        let mut versions: Vec<&Version> = candidates
+            .iter()
+            .filter_map(|candidate| candidate.version.as_ref())
+            .filter(|version| {
+                wants
+                    .version
+                    .as_ref()
+                    .is_none_or(|requested| version.serves(requested))
+            })
+            .collect();
+        versions.sort_unstable_by(|a, b| b.cmp(a));
+        versions.dedup();
+        let versioned = versions.iter().find_map(|&version| {
+            let of_version = candidates
+                .iter()
+                .filter(|candidate| candidate.version.as_ref() == Some(version));
+            best_candidate(of_version, wants)
+        });
+        let unversioned = || {
+            let unversioned = candidates
+                .iter()
+                .filter(|candidate| candidate.version.is_none());
+            best_candidate(unversioned, wants)
+        };
+        if let Some(chosen) = versioned.or_else(unversioned) {
+            return Ok(Some(chosen));
+        }
+        if default.is_some() {
+            return Ok(default);
+        }
+        match &wants.version {
+            Some(requested) if versions.is_empty() => {
+                Err(Unservable::VersionNotAvailable(requested.to_string()))
+            }
+            _ => Err(Unservable::NotAcceptable),
+        }
+    }
+
+    /// The types of handler this leaf has, including candidates, maybe repeated
+    fn handler_types(&self) -> impl Iterator<Item = &HandlerType> {
+        self.asgi_handlers.keys().chain(self.candidates.keys())
+    }
+
+    /// The http methods this leaf has handlers for, sorted
+    fn allowed_methods(&self) -> Vec<&str> {
+        let mut methods: Vec<&str> = self
+            .handler_types()
+            .filter_map(HandlerType::http_method)
+            .collect();
+        methods.sort_unstable();
+        methods.dedup();
+        methods
+    }
+
+    /// The names of all the handlers with one
+    fn names(&self) -> impl Iterator<Item = &String> {
+        self.handler_names.values().chain(
+            self.candidates
+                .values()
+                .flatten()
+                .filter_map(|candidate| candidate.name.as_ref()),
+        )
+    }
+
+    /// Remove the handlers whose type and version match `filter`
+    ///
+    /// Only candidates have a version, the others are passed `None`. Returns `None` if
+    /// nothing was removed, otherwise the names of removed handlers which no remaining
+    /// handler shares.
+    fn remove_handlers(
+        &mut self,
+        filter: &dyn Fn(&HandlerType, Option<&Version>) -> bool,
+    ) -> Option<Vec<String>> {
+        let mut removed = false;
+        let mut removed_names = Vec::new();
+        let handler_names = &mut self.handler_names;
+        self.asgi_handlers.retain(|handler_type, _| {
+            if !filter(handler_type, None) {
+                return true;
+            }
+            removed = true;
+            removed_names.extend(handler_names.remove(handler_type));
+            false
+        });
+        self.candidates.retain(|handler_type, candidates| {
+            candidates.retain_mut(|candidate| {
+                if !filter(handler_type, candidate.version.as_ref()) {
+                    return true;
+                }
+                removed = true;
+                removed_names.extend(candidate.name.take());
+                false
+            });
+            !candidates.is_empty()
+        });
+        if !removed {
+            return None;
         }
+        if !self.asgi_handlers.contains_key(&HandlerType::Asgi)
+            && !self.candidates.contains_key(&HandlerType::Asgi)
+        {
+            self.is_asgi = false;
+        }
+        removed_names.sort_unstable();
+        removed_names.dedup();
+        removed_names.retain(|name| !self.names().any(|other| other == name));
+        Some(removed_names)
     }
 }
 
@@ -76,10 +377,38 @@ impl HandlerType {
             _ => Self::HttpOther(String::from(method)),
         }
     }
+
+    fn http_method(&self) -> Option<&str> {
+        match self {
+            Self::Asgi | Self::Websocket => None,
+            Self::HttpGet => Some("GET"),
+            Self::HttpPost => Some("POST"),
+            Self::HttpDelete => Some("DELETE"),
+            Self::HttpPatch => Some("PATCH"),
+            Self::HttpPut => Some("PUT"),
+            Self::HttpHead => Some("HEAD"),
+            Self::HttpOther(method) => Some(method),
+        }
+    }
 }
 
-fn split_path(path: &str) -> impl Iterator<Item = &'_ str> {
-    path.split('/').filter(|s| !s.is_empty())
+/// The option `key` of a route handler, like its `name` or `opt`, if it's set
+///
+/// Plain ASGI apps, used without starlite, have no options, so they're taken from
+/// the route instead, as set on the built-in route classes.
+fn handler_option<'a>(
+    route: &'a PyAny,
+    handler: &'a PyAny,
+    key: &str,
+) -> PyResult<Option<&'a PyAny>> {
+    for source in [handler, route] {
+        if let Ok(value) = source.getattr(key) {
+            if !value.is_none() {
+                return Ok(Some(value));
+            }
+        }
+    }
+    Ok(None)
 }
 
 fn build_param_set<'a>(
@@ -98,75 +427,421 @@ fn build_param_set<'a>(
 }
 
 impl RouteMap {
-    fn add_routes_(&mut self, items: &PySequence) -> PyResult<()> {
-        let p = items.py();
-        let mut param_strings = HashSet::new();
+    fn add_routes_(&mut self, items: &PySequence, version: Option<&Version>) -> PyResult<()> {
         for route in items.iter()? {
-            let route: &PyAny = route?;
-            let base: BaseRoute = route.extract()?;
-            let path = base.path;
-            let path_parameters: Vec<&PyAny> = base.path_parameters.extract()?;
-
-            let in_static = self.app.path_in_static(p, path)?;
-            let leaf: &mut Leaf = if !path_parameters.is_empty() || in_static {
-                build_param_set(&path_parameters, &mut param_strings)?;
-
-                let mut node = &mut self.param_routes;
-                for s in split_path(path) {
-                    // Could we just assume a path segment that starts and ends
-                    // with `{}` is a placeholder?
-                    let is_placeholder = s.starts_with('{')
-                        && s.ends_with('}')
-                        && param_strings.contains(&s[1..s.len() - 1]);
-
-                    node = if is_placeholder {
-                        node.placeholder_child.get_or_insert_with(Default::default)
-                    } else {
-                        node.children
-                            .entry(String::from(s))
-                            .or_insert_with(Default::default)
-                    };
+            let route = self.new_route(route?, version)?;
+            self.add_route(route)?;
+        }
+        Ok(())
+    }
+
+    /// Build the handlers of `route`, ready to be added with [`RouteMap::add_route`]
+    fn new_route<'py>(
+        &self,
+        route: &'py PyAny,
+        version: Option<&Version>,
+    ) -> PyResult<NewRoute<'py>> {
+        let p = route.py();
+        let base: BaseRoute = route.extract()?;
+        let path = base.path;
+        let path_parameter_list: Vec<&PyAny> = base.path_parameters.extract()?;
+        let mut param_strings = HashSet::new();
+        build_param_set(&path_parameter_list, &mut param_strings)?;
+
+        let mut handlers = Vec::new();
+        let route_types = &self.route_types;
+        let is_asgi = if route.is_instance(route_types.http.as_ref(p))? {
+            let http_route: HttpRoute<'_> = route.extract()?;
+            for (method, (handler, _)) in http_route.route_handler_map {
+                handlers.push((HandlerType::from_http_method(method), handler));
+            }
+            false
+        } else if route.is_instance(route_types.websocket.as_ref(p))? {
+            let SingleHandlerRoute { handler } = route.extract()?;
+            handlers.push((HandlerType::Websocket, handler));
+            false
+        } else if route.is_instance(route_types.asgi.as_ref(p))? {
+            let SingleHandlerRoute { handler } = route.extract()?;
+            handlers.push((HandlerType::Asgi, handler));
+            true
+        } else {
+            return Err(PyTypeError::new_err("Unknown route type"));
+        };
+        let handlers = handlers
+            .into_iter()
+            .map(|(handler_type, handler)| {
+                let name: Option<String> = match handler_option(route, handler, "name")? {
+                    Some(name) => name.extract()?,
+                    None => None,
+                };
+                let predicates = match handler_option(route, handler, "opt")? {
+                    Some(opt) => negotiate::opt_predicates(opt)?,
+                    None => None,
+                };
+                let predicates = match (predicates, version) {
+                    (None, Some(_)) => Some(HeaderPredicates::default()),
+                    (predicates, _) => predicates,
+                };
+                let app = match &self.app {
+                    Some(app) => app.build_route(route, handler)?,
+                    None => handler.into(),
+                };
+                Ok(NewHandler {
+                    handler_type,
+                    app,
+                    name,
+                    predicates,
+                })
+            })
+            .collect::<PyResult<Vec<_>>>()?;
+
+        let in_static = match &self.app {
+            Some(app) => app.path_in_static(p, path)?,
+            None => false,
+        };
+        Ok(NewRoute {
+            path,
+            path_parameters: base.path_parameters,
+            path_parameter_list,
+            param_strings,
+            version: version.cloned(),
+            is_asgi,
+            in_static,
+            handlers,
+        })
+    }
+
+    /// Add the handlers of `route` to the route at its path
+    fn add_route(&mut self, route: NewRoute<'_>) -> PyResult<()> {
+        let p = route.path_parameters.py();
+        let path = route.path;
+        let leaf = &mut self
+            .route_entry(
+                path,
+                route.path_parameters,
+                &route.param_strings,
+                route.in_static,
+            )?
+            .value;
+        leaf.is_asgi |= route.is_asgi || route.in_static;
+
+        let mut names = Vec::new();
+        for NewHandler {
+            handler_type,
+            app,
+            name,
+            predicates,
+        } in route.handlers
+        {
+            if let Some(predicates) = predicates {
+                names.extend(name.clone());
+                leaf.candidates
+                    .entry(handler_type)
+                    .or_default()
+                    .push(Candidate {
+                        predicates,
+                        version: route.version.clone(),
+                        handler: app,
+                        name,
+                    });
+                continue;
+            }
+            leaf.asgi_handlers.insert(handler_type.clone(), app);
+            match name {
+                Some(name) => {
+                    leaf.handler_names.insert(handler_type, name.clone());
+                    names.push(name);
+                }
+                None => {
+                    leaf.handler_names.remove(&handler_type);
                 }
-                // Found where the leaf should be, get it, or add a new one
-                node.leaf
-                    .get_or_insert_with(|| Leaf::new(base.path_parameters.into()))
-            } else {
-                self.plain_routes
-                    .entry(String::from(path))
-                    .or_insert_with(|| Leaf::new(base.path_parameters.into()))
-            };
-            if leaf.path_parameters.as_ref(p).ne(base.path_parameters)? {
-                return Err(exceptions::ImproperlyConfiguredException::new_err(
-                    "Routes with conflicting path parameters",
-                ));
             }
-            if in_static {
-                leaf.is_asgi = true;
-                leaf.static_path = Some(String::from(path));
+        }
+        for name in names {
+            self.add_name(
+                p,
+                name,
+                path,
+                &route.path_parameter_list,
+                &route.param_strings,
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Parse the API version given to `add_routes` or `replace_route`
+    fn parse_version(&self, py: Python<'_>, version: Option<&str>) -> PyResult<Option<Version>> {
+        version
+            .map(|version| {
+                Version::parse(version).ok_or_else(|| {
+                    self.exceptions
+                        .improperly_configured(py, format!("Invalid API version {:?}", version))
+                })
+            })
+            .transpose()
+    }
+
+    /// Get the route at `path`, adding it if there isn't one yet
+    ///
+    /// If `mount` is set, the route handles any path below it too.
+    fn route_entry(
+        &mut self,
+        path: &str,
+        path_parameters: &PyAny,
+        param_strings: &HashSet<&str>,
+        mount: bool,
+    ) -> PyResult<&mut Route<Leaf>> {
+        let py = path_parameters.py();
+        let leaf = Leaf::new(path_parameters, &self.param_types)?;
+        let route = self
+            .routes
+            .get_or_insert_with(path, |full| param_strings.contains(full), mount, || leaf)
+            .map_err(|e| self.exceptions.route_error(py, e))?;
+        if route.value.path_parameters.as_ref(py).ne(path_parameters)? {
+            return Err(self.exceptions.improperly_configured(
+                py,
+                String::from("Routes with conflicting path parameters"),
+            ));
+        }
+        Ok(route)
+    }
+
+    /// Check that [`RouteMap::route_entry`] can get or add the route at `path`, without
+    /// changing anything
+    fn check_route_entry(
+        &self,
+        path: &str,
+        path_parameters: &PyAny,
+        param_strings: &HashSet<&str>,
+        mount: bool,
+    ) -> PyResult<()> {
+        let py = path_parameters.py();
+        let existing = self
+            .routes
+            .check_insert(path, |full| param_strings.contains(full), mount)
+            .map_err(|e| self.exceptions.route_error(py, e))?;
+        if let Some(route) = existing {
+            if route.value.path_parameters.as_ref(py).ne(path_parameters)? {
+                return Err(self.exceptions.improperly_configured(
+                    py,
+                    String::from("Routes with conflicting path parameters"),
+                ));
             }
+        }
+        Ok(())
+    }
 
-            let route_types = &self.route_types;
-            if route.is_instance(route_types.http.as_ref(p))? {
-                let http_route: HttpRoute<'_> = route.extract()?;
-                for (method, (handler, _)) in http_route.route_handler_map {
-                    leaf.asgi_handlers.insert(
-                        HandlerType::from_http_method(method),
-                        self.app.build_route(route, handler)?,
-                    );
+    /// Record that a handler named `name` is registered at `path`, for `url_path_for`
+    fn add_name(
+        &mut self,
+        py: Python<'_>,
+        name: String,
+        path: &str,
+        path_parameters: &[&PyAny],
+        param_strings: &HashSet<&str>,
+    ) -> PyResult<()> {
+        let templates = self.names.entry(name).or_default();
+        if templates.iter().all(|template| template.path != path) {
+            templates.push(RouteTemplate::new(
+                py,
+                path,
+                path_parameters,
+                param_strings,
+            )?);
+        }
+        Ok(())
+    }
+
+    /// Remove handlers matching `filter`, as in [`Leaf::remove_handlers`], from the
+    /// route registered at `path`
+    ///
+    /// Returns whether anything was removed.
+    fn remove_route_(
+        &mut self,
+        py: Python<'_>,
+        path: &str,
+        filter: &dyn Fn(&HandlerType, Option<&Version>) -> bool,
+    ) -> PyResult<bool> {
+        let route = match self
+            .routes
+            .get_mut(path)
+            .map_err(|e| self.exceptions.route_error(py, e))?
+        {
+            Some(route) => route,
+            None => return Ok(false),
+        };
+        let removed_names = match route.value.remove_handlers(filter) {
+            Some(names) => names,
+            None => return Ok(false),
+        };
+        if !route.value.is_asgi {
+            route.unmount();
+        }
+        // The names were recorded with the path the route was added at, which may
+        // differ from `path` by its trailing slash
+        let route_path = String::from(route.path());
+        if route.value.is_empty() {
+            self.routes
+                .remove(path)
+                .map_err(|e| self.exceptions.route_error(py, e))?;
+        }
+        let trailing_slash = self.routes.trailing_slash();
+        let route_path = trailing_slash.route_path(&route_path);
+        for name in removed_names {
+            if let Some(templates) = self.names.get_mut(&name) {
+                templates
+                    .retain(|template| trailing_slash.route_path(&template.path) != route_path);
+                if templates.is_empty() {
+                    self.names.remove(&name);
                 }
-            } else if route.is_instance(route_types.websocket.as_ref(p))? {
-                let SingleHandlerRoute { handler } = route.extract()?;
-                leaf.asgi_handlers.insert(
-                    HandlerType::Websocket,
-                    self.app.build_route(route, handler)?,
-                );
-            } else if route.is_instance(route_types.asgi.as_ref(p))? {
-                let SingleHandlerRoute { handler } = route.extract()?;
-                leaf.asgi_handlers
-                    .insert(HandlerType::Asgi, self.app.build_route(route, handler)?);
-                leaf.is_asgi = true;
+            }
+        }
+        Ok(true)
+    }
+
+    fn replace_route_(&mut self, route: &PyAny, version: Option<&Version>) -> PyResult<()> {
+        let py = route.py();
+        let BaseRoute { path, .. } = route.extract()?;
+        let route_types = &self.route_types;
+        let kind: &dyn Fn(&HandlerType) -> bool =
+            if route.is_instance(route_types.http.as_ref(py))? {
+                &|handler_type| handler_type.http_method().is_some()
+            } else if route.is_instance(route_types.websocket.as_ref(py))? {
+                &|handler_type| *handler_type == HandlerType::Websocket
+            } else if route.is_instance(route_types.asgi.as_ref(py))? {
+                &|handler_type| *handler_type == HandlerType::Asgi
             } else {
-                return Err(PyTypeError::new_err("Unknown route type"));
+                &|_| false
+            };
+        // Make sure the new route can be added before removing what it replaces
+        let new_route = self.new_route(route, version)?;
+        self.check_route_entry(
+            path,
+            new_route.path_parameters,
+            &new_route.param_strings,
+            new_route.in_static,
+        )?;
+        self.remove_route_(py, path, &|handler_type, handler_version| {
+            kind(handler_type) && handler_version == version
+        })?;
+        self.add_route(new_route)
+    }
+
+    fn mount_(&mut self, py: Python<'_>, prefix: &str, app: Py<ASGIApp>) -> PyResult<()> {
+        let mut leaf = Leaf::new(PyList::empty(py), &self.param_types)?;
+        leaf.is_asgi = true;
+        leaf.asgi_handlers.insert(HandlerType::Asgi, app);
+        self.routes
+            .mount(prefix, leaf)
+            .map_err(|e| self.exceptions.route_error(py, e))
+    }
+
+    /// Copy all the routes of `other` into this map, below `prefix`
+    fn include_(&mut self, py: Python<'_>, prefix: &str, other: &RouteMap) -> PyResult<()> {
+        let segments = starlite_router_core::literal_prefix(prefix)
+            .map_err(|e| self.exceptions.route_error(py, e))?;
+        let prefixed = |path: &str| match (segments.is_empty(), path) {
+            (true, path) => String::from(path),
+            (false, "/") => format!("/{}", segments.join("/")),
+            (false, path) => format!("/{}{}", segments.join("/"), path),
+        };
+        let routes = other.routes.routes();
+
+        // Check that every route can be added before adding any, against the routes
+        // here and the other included routes, since with a different trailing slash
+        // mode they can conflict with each other here
+        let mut included = Router::new(self.routes.trailing_slash());
+        let mut param_strings = HashSet::new();
+        for route in &routes {
+            let path = prefixed(route.path());
+            let path_parameters = route.value.path_parameters.as_ref(py);
+            let path_parameter_list: Vec<&PyAny> = path_parameters.extract()?;
+            build_param_set(&path_parameter_list, &mut param_strings)?;
+            self.check_route_entry(&path, path_parameters, &param_strings, route.is_mount())?;
+            included
+                .get_or_insert_with(
+                    &path,
+                    |full| param_strings.contains(full),
+                    route.is_mount(),
+                    || (),
+                )
+                .map_err(|e| self.exceptions.route_error(py, e))?;
+
+            let existing = self
+                .routes
+                .get(&path)
+                .map_err(|e| self.exceptions.route_error(py, e))?;
+            if let Some(existing) = existing.map(|existing| &existing.value) {
+                let leaf = &route.value;
+                // Candidates would shadow the other map's handlers of the same type, so
+                // they conflict with them like any handler
+                let conflicts = existing.is_asgi
+                    || leaf.is_asgi
+                    || leaf.handler_types().any(|handler_type| {
+                        existing.asgi_handlers.contains_key(handler_type)
+                            || existing.candidates.contains_key(handler_type)
+                    });
+                if conflicts {
+                    return Err(self.exceptions.improperly_configured(
+                        py,
+                        format!("Included route {:?} conflicts with an existing route", path),
+                    ));
+                }
+            }
+        }
+
+        self.include_routes(py, routes, &prefixed)
+    }
+
+    /// Copy `routes` into this map, at the paths given by `prefixed`
+    fn include_routes(
+        &mut self,
+        py: Python<'_>,
+        routes: Vec<&Route<Leaf>>,
+        prefixed: &dyn Fn(&str) -> String,
+    ) -> PyResult<()> {
+        let mut param_strings = HashSet::new();
+        for route in routes {
+            let leaf = &route.value;
+            let path = prefixed(route.path());
+            let path_parameters = leaf.path_parameters.as_ref(py);
+            let path_parameter_list: Vec<&PyAny> = path_parameters.extract()?;
+            build_param_set(&path_parameter_list, &mut param_strings)?;
+
+            let new_leaf = &mut self
+                .route_entry(&path, path_parameters, &param_strings, route.is_mount())?
+                .value;
+            new_leaf.is_asgi |= leaf.is_asgi;
+            for (handler_type, app) in &leaf.asgi_handlers {
+                new_leaf
+                    .asgi_handlers
+                    .insert(handler_type.clone(), app.clone_ref(py));
+            }
+            for (handler_type, name) in &leaf.handler_names {
+                new_leaf
+                    .handler_names
+                    .insert(handler_type.clone(), name.clone());
+            }
+            for (handler_type, candidates) in &leaf.candidates {
+                new_leaf
+                    .candidates
+                    .entry(handler_type.clone())
+                    .or_default()
+                    .extend(candidates.iter().map(|candidate| Candidate {
+                        predicates: candidate.predicates.clone(),
+                        version: candidate.version.clone(),
+                        handler: candidate.handler.clone_ref(py),
+                        name: candidate.name.clone(),
+                    }));
+            }
+            for name in leaf.names() {
+                self.add_name(
+                    py,
+                    name.clone(),
+                    &path,
+                    &path_parameter_list,
+                    &param_strings,
+                )?;
             }
         }
         Ok(())
@@ -174,86 +849,329 @@ impl RouteMap {
 
     fn resolve_route_(&self, scope: &PyMapping) -> PyResult<Py<PyAny>> {
         let py = scope.py();
-        let path: &str = scope.get_item(pyo3::intern!(py, "path"))?.extract()?;
-        let mut path = path.strip_suffix(|ch| ch == '/').unwrap_or(path);
-        if path.is_empty() {
-            path = "/";
-        }
-        let (leaf, params) = match self.plain_routes.get(path) {
-            Some(leaf) => (leaf, PyList::empty(py)),
-            None => self.find_route(path, scope)?,
-        };
-        scope.set_item(
-            pyo3::intern!(py, "path_params"),
-            self.parse_path_params(leaf.path_parameters.as_ref(py), params)?,
-        )?;
+        match self.dispatch(scope)? {
+            Resolved::Handler(handler) => Ok(handler),
+            Resolved::NotFound => Err(self.exceptions.not_found(py)),
+            Resolved::MethodNotAllowed(methods) => {
+                let methods: Vec<&str> = methods.iter().map(String::as_str).collect();
+                Err(self.exceptions.method_not_allowed(py, &methods))
+            }
+            Resolved::BadRequest => Err(self.exceptions.malformed_path(py)),
+            Resolved::VersionNotAvailable(requested) => {
+                Err(self.exceptions.version_not_available(py, &requested))
+            }
+            Resolved::NotAcceptable => Err(self.exceptions.not_acceptable(py)),
+        }
+    }
 
-        let handler: Option<&Py<ASGIApp>> = if leaf.is_asgi {
-            leaf.asgi_handlers.get(&HandlerType::Asgi)
+    /// Route `scope`, setting its path parameters and any path or root path changes
+    /// for the handler
+    ///
+    /// Scopes which aren't http or websocket ones are given the handler registered for
+    /// their type.
+    fn dispatch(&self, scope: &PyMapping) -> PyResult<Resolved> {
+        let py = scope.py();
+        let scope_type: &str = scope.get_item(pyo3::intern!(py, "type"))?.extract()?;
+        if !is_routed(scope_type) {
+            return match self.scope_handlers.get(scope_type) {
+                Some(handler) => Ok(Resolved::Handler(handler.clone_ref(py))),
+                None => Err(self.exceptions.unsupported_scope_type(py, scope_type)),
+            };
+        }
+        let host = if self.hosts.is_empty() {
+            None
         } else {
-            let scope_type: &str = scope.get_item(pyo3::intern!(py, "type"))?.extract()?;
-            if scope_type == "http" {
-                let scope_method: &str = scope.get_item(pyo3::intern!(py, "method"))?.extract()?;
-                let handler = leaf
-                    .asgi_handlers
-                    .get(&HandlerType::from_http_method(scope_method));
-                if handler.is_none() {
-                    return Err(exceptions::MethodNotAllowedException::new_err(()));
+            host::scope_host(scope)?
+        };
+        if let Some((routes, host_params)) = host.as_deref().and_then(|host| self.host_routes(host))
+        {
+            let resolved = routes.borrow(py).dispatch(scope)?;
+            if let Resolved::Handler(_) = resolved {
+                if let Ok(path_params) = scope.get_item(pyo3::intern!(py, "path_params")) {
+                    add_host_params(path_params.downcast()?, &host_params)?;
                 }
-                handler
-            } else {
-                leaf.asgi_handlers.get(&HandlerType::Websocket)
             }
+            return Ok(resolved);
+        }
+        let key_path = pyo3::intern!(py, "path");
+        let key_raw_path = pyo3::intern!(py, "raw_path");
+        let request_path: &str = scope.get_item(key_path)?.extract()?;
+        let request_raw_path: Option<&[u8]> = match scope.get_item(key_raw_path) {
+            Ok(raw_path) => raw_path.extract()?,
+            Err(_) => None,
+        };
+        let Requested {
+            version,
+            prefix: version_prefix,
+        } = match &self.options.versioning {
+            Some(versioning) => match versioning.requested(scope, request_path)? {
+                Ok(requested) => requested,
+                Err(requested) => return Ok(Resolved::VersionNotAvailable(requested)),
+            },
+            None => Requested::default(),
+        };
+        // With path versioning, the rest of the path is routed, and only moved to the
+        // scope once it matches a route
+        let (scope_path, raw_path) = match version_prefix {
+            Some(prefix) => {
+                let rest = &request_path[prefix.len()..];
+                let raw_rest = request_raw_path.map(|raw_path| {
+                    match raw_path.strip_prefix(prefix.as_bytes()) {
+                        Some(b"") => b"/",
+                        Some(rest) => rest,
+                        None => raw_path,
+                    }
+                });
+                (if rest.is_empty() { "/" } else { rest }, raw_rest)
+            }
+            None => (request_path, request_raw_path),
+        };
+        let raw = match raw_path {
+            Some(raw_path) if self.options.match_raw_path => {
+                let decoded =
+                    RawPath::decode(self.options.path_normalization, raw_path, |segments| {
+                        self.routes.is_mount(segments)
+                    });
+                match decoded {
+                    Ok(raw) => Some(raw),
+                    Err(e) => return Ok(e.into()),
+                }
+            }
+            _ => None,
+        };
+        // Decoded segments containing slashes can't be joined back into the path the
+        // handler sees, so it gets the scope's own path then, normalized the same way
+        let normalized = match &raw {
+            Some(raw) if !raw.has_slash_in_segment() => Cow::Borrowed(raw.path.as_str()),
+            _ => match self.normalize_path(scope_path, raw_path.filter(|_| raw.is_none())) {
+                Ok(path) => path,
+                Err(e) => return Ok(e.into()),
+            },
+        };
+        let scope_path: &str = &normalized;
+        let found = match &raw {
+            Some(raw) => self.routes.find_raw(raw),
+            None => self.routes.find(scope_path),
+        };
+        let Match { route, params } = match found {
+            Some(found) => found,
+            None if raw.as_ref().is_some_and(RawPath::has_slash_in_segment) => {
+                return Ok(Resolved::NotFound)
+            }
+            None => {
+                let path = self.routes.trailing_slash().route_path(scope_path);
+                return Ok(self
+                    .trailing_slash_redirect(scope, version_prefix.unwrap_or(""), path)?
+                    .map_or(Resolved::NotFound, Resolved::Handler));
+            }
+        };
+        let leaf = &route.value;
+        let path_params =
+            match params::parse_path_params(py, &self.param_types, &leaf.params, &params)? {
+                Some(path_params) => path_params,
+                None => return Ok(Resolved::NotFound),
+            };
+        scope.set_item(pyo3::intern!(py, "path_params"), path_params)?;
+        let (mount_prefix, handler_path) =
+            route.split_mount(scope_path).unwrap_or(("", scope_path));
+        let root_prefix = format!("{}{}", version_prefix.unwrap_or(""), mount_prefix);
+        if !root_prefix.is_empty() {
+            let key_root_path = pyo3::intern!(py, "root_path");
+            let root_path: &str = match scope.get_item(key_root_path) {
+                Ok(root_path) => root_path.extract()?,
+                Err(_) => "",
+            };
+            scope.set_item(key_root_path, format!("{}{}", root_path, root_prefix))?;
+        }
+        if handler_path != request_path {
+            scope.set_item(key_path, handler_path)?;
+        }
+        if let Some(raw_path) = raw_path.filter(|&raw_path| Some(raw_path) != request_raw_path) {
+            scope.set_item(key_raw_path, PyBytes::new(py, raw_path))?;
+        }
+
+        let method: Option<&str> = if scope_type == "http" {
+            Some(scope.get_item(pyo3::intern!(py, "method"))?.extract()?)
+        } else {
+            None
         };
-        let handler: Py<ASGIApp> = handler
-            .ok_or_else(|| exceptions::NotFoundException::new_err(()))?
-            .clone_ref(py);
-        Ok(handler)
+        let wants = Wants {
+            headers: if leaf.candidates.is_empty() {
+                Vec::new()
+            } else {
+                negotiate::scope_headers(scope)?
+            },
+            subprotocols: if scope_type == "websocket" && !leaf.candidates.is_empty() {
+                negotiate::scope_subprotocols(scope)?
+            } else {
+                Vec::new()
+            },
+            version,
+        };
+        match self.select_handler(py, leaf, scope_type, method, &wants)? {
+            Ok(Some((handler, subprotocol))) => {
+                if let Some(subprotocol) = subprotocol {
+                    scope.set_item(pyo3::intern!(py, "subprotocol"), subprotocol)?;
+                }
+                Ok(Resolved::Handler(handler))
+            }
+            Ok(None) if method.is_some() && !leaf.is_asgi => {
+                let methods = self.allowed_methods(leaf);
+                // Only websocket handlers, so there's nothing to allow an http request
+                if methods.is_empty() {
+                    return Ok(Resolved::NotFound);
+                }
+                Ok(Resolved::MethodNotAllowed(
+                    methods.into_iter().map(String::from).collect(),
+                ))
+            }
+            Ok(None) => Ok(Resolved::NotFound),
+            Err(unservable) => Ok(unservable.into()),
+        }
+    }
+
+    /// The route map for requests to `host`, if it isn't this one, and the values of
+    /// the host's parameters
+    fn host_routes<'h>(&self, host: &'h str) -> Option<(&Py<RouteMap>, HostParams<'_, 'h>)> {
+        self.hosts
+            .iter()
+            .find_map(|(pattern, routes)| Some((routes, pattern.match_host(host)?)))
     }
 
-    fn find_route<'a>(&'a self, path: &str, scope: &'a PyMapping) -> PyResult<(&Leaf, &PyList)> {
+    fn normalize_path<'p>(
+        &self,
+        path: &'p str,
+        raw_path: Option<&[u8]>,
+    ) -> Result<Cow<'p, str>, normalize::PathError> {
+        normalize::normalize_path(
+            self.options.path_normalization,
+            path,
+            raw_path,
+            |segments| self.routes.is_mount(segments),
+        )
+    }
+
+    /// When redirecting on trailing slashes, get a responder redirecting an http
+    /// request for `path` to the same path with its trailing slash toggled, if that
+    /// matches a route
+    ///
+    /// `prefix` is the part of the request's path before `path`, like an API version.
+    fn trailing_slash_redirect(
+        &self,
+        scope: &PyMapping,
+        prefix: &str,
+        path: &str,
+    ) -> PyResult<Option<Py<ASGIApp>>> {
         let py = scope.py();
-        let key_path = pyo3::intern!(py, "path");
-        let mut params = Vec::new();
-        let mut node = &self.param_routes;
-        for component in split_path(path) {
-            if let Some(child) = node.children.get(component) {
-                node = child;
-                continue;
+        let status = match self.routes.trailing_slash() {
+            TrailingSlash::Redirect(status) => status,
+            _ => return Ok(None),
+        };
+        let scope_type: &str = scope.get_item(pyo3::intern!(py, "type"))?.extract()?;
+        if scope_type != "http" {
+            return Ok(None);
+        }
+        let other = match self.routes.trailing_slash_redirect(path) {
+            Some(other) => other,
+            None => return Ok(None),
+        };
+
+        let root_path: &str = match scope.get_item(pyo3::intern!(py, "root_path")) {
+            Ok(root_path) => root_path.extract()?,
+            Err(_) => "",
+        };
+        let mut location = format!("{}{}{}", root_path, prefix, other);
+        if let Ok(query_string) = scope.get_item(pyo3::intern!(py, "query_string")) {
+            let query_string: &[u8] = query_string.extract()?;
+            if !query_string.is_empty() {
+                location.push('?');
+                location.push_str(&String::from_utf8_lossy(query_string));
             }
-            if let Some(child) = &node.placeholder_child {
-                node = child;
-                params.push(component);
-                continue;
+        }
+        let responder = Responder::redirect(status, location);
+        Ok(Some(Py::new(py, responder)?.into_py(py)))
+    }
+
+    /// Get the handler of `leaf` for a scope, including any automatic `HEAD` or
+    /// `OPTIONS` handling, with the websocket subprotocol it was chosen for
+    ///
+    /// Requests no handler can serve are `Unservable`, as in [`Leaf::choose`].
+    fn select_handler<'a>(
+        &self,
+        py: Python<'_>,
+        leaf: &'a Leaf,
+        scope_type: &str,
+        method: Option<&str>,
+        wants: &Wants<'_>,
+    ) -> PyResult<Result<Option<Selected<'a>>, Unservable>> {
+        let chosen = match leaf.handler(scope_type, method, wants) {
+            Ok(chosen) => chosen,
+            Err(unservable) => return Ok(Err(unservable)),
+        };
+        if let Some(chosen) = chosen {
+            return Ok(Ok(Some((chosen.handler.clone_ref(py), chosen.subprotocol))));
+        }
+        if leaf.is_asgi {
+            return Ok(Ok(None));
+        }
+        let handler = match method {
+            Some("HEAD") if self.options.auto_head => {
+                match leaf.choose(&HandlerType::HttpGet, wants) {
+                    Ok(chosen) => chosen.map(|chosen| (chosen.handler.clone_ref(py), None)),
+                    Err(unservable) => return Ok(Err(unservable)),
+                }
             }
-            let static_path = node
-                .leaf
-                .as_ref()
-                .and_then(|leaf| leaf.static_path.as_deref());
-            if let Some(static_path) = static_path {
-                if static_path != "/" {
-                    let old_scope_path: &str = scope.get_item(key_path)?.extract()?;
-                    let new_scope_path = old_scope_path.replace(static_path, "");
-                    scope.set_item(key_path, new_scope_path)?;
+            Some("OPTIONS") if self.options.auto_options => {
+                let methods = self.allowed_methods(leaf);
+                if methods.is_empty() {
+                    None
+                } else {
+                    let responder = Responder::options(&methods);
+                    Some((Py::new(py, responder)?.into_py(py), None))
                 }
-                continue;
             }
-
-            return Err(exceptions::NotFoundException::new_err(()));
-        }
-        let leaf = match &node.leaf {
-            Some(leaf) => leaf,
-            None => return Err(exceptions::NotFoundException::new_err(())),
+            _ => None,
         };
-        let list = PyList::new(py, params);
-        Ok((leaf, list))
+        Ok(Ok(handler))
     }
 
-    fn parse_path_params(&self, params: &PyAny, values: &PyList) -> PyResult<Py<PyAny>> {
-        self.path_param_parser.call1(params.py(), (params, values))
+    /// The http methods `leaf` will respond to, sorted
+    fn allowed_methods<'a>(&self, leaf: &'a Leaf) -> Vec<&'a str> {
+        let mut methods = leaf.allowed_methods();
+        if leaf.is_asgi || methods.is_empty() {
+            return methods;
+        }
+        if self.options.auto_head && methods.contains(&"GET") {
+            methods.push("HEAD");
+        }
+        if self.options.auto_options {
+            methods.push("OPTIONS");
+        }
+        methods.sort_unstable();
+        methods.dedup();
+        methods
     }
 }
 
+/// A route from Python, with its handlers built, to be added to a `RouteMap`
+struct NewRoute<'py> {
+    path: &'py str,
+    path_parameters: &'py PyAny,
+    path_parameter_list: Vec<&'py PyAny>,
+    param_strings: HashSet<&'py str>,
+    version: Option<Version>,
+    is_asgi: bool,
+    in_static: bool,
+    handlers: Vec<NewHandler>,
+}
+
+struct NewHandler {
+    handler_type: HandlerType,
+    app: Py<PyAny>,
+    name: Option<String>,
+    predicates: Option<HeaderPredicates>,
+}
+
 #[derive(Debug, FromPyObject)]
 struct BaseRoute<'a> {
     path: &'a str,
@@ -289,36 +1207,84 @@ impl StarliteApp {
     }
 }
 
-#[derive(Debug, Clone)]
-struct RouteTypes {
-    http: Py<PyType>,
-    websocket: Py<PyType>,
-    asgi: Py<PyType>,
-}
-
 #[pymethods]
 impl RouteMap {
     #[new]
-    fn new(py: Python<'_>, app: StarliteApp) -> PyResult<Self> {
-        let module = py.import("starlite.routes")?;
-        let extract_type = |name: &str| -> PyResult<Py<PyType>> {
-            let any: &PyAny = module.getattr(name)?;
-            Ok(any.downcast::<PyType>()?.into())
-        };
-        let route_types = RouteTypes {
-            http: extract_type("HTTPRoute")?,
-            websocket: extract_type("WebSocketRoute")?,
-            asgi: extract_type("ASGIRoute")?,
-        };
+    #[args(
+        app = "None",
+        "*",
+        auto_head = "false",
+        auto_options = "false",
+        trailing_slash = "\"lenient\"",
+        path_normalization = "\"pass_through\"",
+        match_raw_path = "false",
+        versioning = "None",
+        version_param = "None",
+        route_types = "None",
+        exceptions = "None",
+        not_found = "None",
+        method_not_allowed = "None",
+        lifespan = "None"
+    )]
+    // Each option is a keyword argument
+    #[allow(clippy::too_many_arguments)]
+    fn new(
+        py: Python<'_>,
+        app: Option<StarliteApp>,
+        auto_head: bool,
+        auto_options: bool,
+        trailing_slash: &str,
+        path_normalization: &str,
+        match_raw_path: bool,
+        versioning: Option<&str>,
+        version_param: Option<&str>,
+        route_types: Option<&PyDict>,
+        exceptions: Option<&PyDict>,
+        not_found: Option<Py<ASGIApp>>,
+        method_not_allowed: Option<Py<ASGIApp>>,
+        lifespan: Option<Py<ASGIApp>>,
+    ) -> PyResult<Self> {
+        let route_types = RouteTypes::new(py, route_types)?;
+        let exceptions = Exceptions::new(py, exceptions)?;
+        let trailing_slash = TrailingSlash::from_name(trailing_slash).ok_or_else(|| {
+            PyValueError::new_err(format!(
+                "Unknown trailing_slash mode {:?}, expected one of \"lenient\", \"strict\", \
+                 \"redirect\" or \"redirect_permanent\"",
+                trailing_slash
+            ))
+        })?;
+        let path_normalization =
+            PathNormalization::from_name(path_normalization).ok_or_else(|| {
+                PyValueError::new_err(format!(
+                    "Unknown path_normalization mode {:?}, expected one of \"pass_through\", \
+                     \"normalize\" or \"reject\"",
+                    path_normalization
+                ))
+            })?;
 
-        let module = py.import("starlite.parsers")?;
-        let path_param_parser = module.getattr("parse_path_params")?.into();
         Ok(Self {
             app,
             route_types,
-            path_param_parser,
-            param_routes: Node::default(),
-            plain_routes: HashMap::default(),
+            param_types: ParamTypes::new(py)?,
+            routes: Router::new(trailing_slash),
+            names: HashMap::default(),
+            options: Options {
+                auto_head,
+                auto_options,
+                path_normalization,
+                match_raw_path,
+                versioning: versioning
+                    .map(|strategy| Versioning::from_options(strategy, version_param))
+                    .transpose()?,
+                not_found,
+                method_not_allowed,
+            },
+            hosts: Vec::new(),
+            scope_handlers: lifespan
+                .map(|lifespan| (String::from("lifespan"), lifespan))
+                .into_iter()
+                .collect(),
+            exceptions,
         })
     }
 
@@ -326,21 +1292,337 @@ impl RouteMap {
         format!("{:#?}", self)
     }
 
-    /// Add an item
-    #[pyo3(text_signature = "(routes)")]
-    fn add_routes(&mut self, routes: &PySequence) -> PyResult<()> {
-        self.add_routes_(routes)
+    /// Add routes, optionally as those of an API version
+    #[pyo3(text_signature = "(routes, *, version=None)")]
+    #[args(routes, "*", version = "None")]
+    fn add_routes(&mut self, routes: &PySequence, version: Option<&str>) -> PyResult<()> {
+        let version = self.parse_version(routes.py(), version)?;
+        self.add_routes_(routes, version.as_ref())
+    }
+
+    /// Remove the route at `path`, or only its handlers for `methods`
+    #[pyo3(text_signature = "(path, methods=None)")]
+    #[args(methods = "None")]
+    fn remove_route(
+        &mut self,
+        py: Python<'_>,
+        path: &str,
+        methods: Option<Vec<&str>>,
+    ) -> PyResult<()> {
+        let removed = match methods {
+            Some(methods) => {
+                // Upper-cased like the methods of an `HTTPRoute`
+                let methods: Vec<HandlerType> = methods
+                    .into_iter()
+                    .map(|method| HandlerType::from_http_method(&method.to_ascii_uppercase()))
+                    .collect();
+                self.remove_route_(py, path, &|handler_type, _| methods.contains(handler_type))?
+            }
+            None => self.remove_route_(py, path, &|_, _| true)?,
+        };
+        if !removed {
+            return Err(PyKeyError::new_err(format!(
+                "No route registered at {:?}",
+                path
+            )));
+        }
+        Ok(())
+    }
+
+    /// Replace the route at the path of `route` with `route`
+    ///
+    /// Only handlers of the same kind and API version are replaced: an http route
+    /// replaces all http handlers of its version at its path, but leaves a websocket
+    /// handler there, and handlers of other versions. Without a version, only the
+    /// handlers which have none are replaced.
+    #[pyo3(text_signature = "(route, *, version=None)")]
+    #[args(route, "*", version = "None")]
+    fn replace_route(&mut self, route: &PyAny, version: Option<&str>) -> PyResult<()> {
+        let version = self.parse_version(route.py(), version)?;
+        self.replace_route_(route, version.as_ref())
     }
 
     #[pyo3(text_signature = "(scope)")]
     fn resolve_route(&self, scope: &PyMapping) -> PyResult<Py<PyAny>> {
         self.resolve_route_(scope)
     }
+
+    /// Handle a request as an ASGI app, forwarding it to the handler of its route
+    ///
+    /// Requests matching no route, or for an API version which isn't available, get a
+    /// plain 404 response, and those for a method their route has no handler for a
+    /// 405 (or a 404, if it only has websocket handlers), unless other apps are given
+    /// to respond with. Requests with a malformed path get a 400 response, and those no
+    /// handler can produce an acceptable response for a 406. The methods which are
+    /// allowed are set as the scope's `allowed_methods` for the app responding with a
+    /// 405. Lifespan scopes are passed to the lifespan handler, if there is one, and
+    /// otherwise each of their events is completed.
+    fn __call__(
+        &self,
+        py: Python<'_>,
+        scope: &PyMapping,
+        receive: &PyAny,
+        send: &PyAny,
+    ) -> PyResult<PyObject> {
+        let scope_type: &str = scope.get_item(pyo3::intern!(py, "type"))?.extract()?;
+        if scope_type == "lifespan" && !self.scope_handlers.contains_key(scope_type) {
+            return Ok(Py::new(py, Coroutine::new(Lifespan::new(receive, send)))?.into_py(py));
+        }
+        let app = match self.dispatch(scope)? {
+            Resolved::Handler(handler) => handler,
+            // An unavailable version is a kind of missing resource
+            Resolved::NotFound | Resolved::VersionNotAvailable(_) => {
+                match &self.options.not_found {
+                    Some(not_found) => not_found.clone_ref(py),
+                    None => Py::new(py, Responder::not_found())?.into_py(py),
+                }
+            }
+            Resolved::MethodNotAllowed(methods) => match &self.options.method_not_allowed {
+                Some(method_not_allowed) => {
+                    scope.set_item(pyo3::intern!(py, "allowed_methods"), methods)?;
+                    method_not_allowed.clone_ref(py)
+                }
+                None => {
+                    let methods: Vec<&str> = methods.iter().map(String::as_str).collect();
+                    Py::new(py, Responder::method_not_allowed(&methods))?.into_py(py)
+                }
+            },
+            Resolved::BadRequest => Py::new(py, Responder::bad_request())?.into_py(py),
+            Resolved::NotAcceptable => Py::new(py, Responder::not_acceptable())?.into_py(py),
+        };
+        app.call1(py, (scope, receive, send))
+    }
+
+    /// Match `path` without modifying any scope, returning `None` if no route matches
+    ///
+    /// If `host` is given, the routes for that host are used.
+    #[pyo3(text_signature = "(path, method=None, scope_type=\"http\", host=None)")]
+    #[args(method = "None", scope_type = "\"http\"", host = "None")]
+    fn r#match(
+        &self,
+        py: Python<'_>,
+        path: &str,
+        method: Option<&str>,
+        scope_type: &str,
+        host: Option<&str>,
+    ) -> PyResult<Option<RouteMatch>> {
+        if let Some((routes, host_params)) = host.and_then(|host| self.host_routes(host)) {
+            let found = routes
+                .borrow(py)
+                .r#match(py, path, method, scope_type, None)?;
+            if let Some(found) = &found {
+                add_host_params(found.path_params.as_ref(py), &host_params)?;
+                add_host_params(found.raw_path_params.as_ref(py), &host_params)?;
+            }
+            return Ok(found);
+        }
+        let path = match self.normalize_path(path, None) {
+            Ok(path) => path,
+            Err(_) => return Ok(None),
+        };
+        let path: &str = &path;
+        let Match { route, params } = match self.routes.find(path) {
+            Some(found) => found,
+            None => return Ok(None),
+        };
+        let leaf = &route.value;
+        let path_params =
+            match params::parse_path_params(py, &self.param_types, &leaf.params, &params)? {
+                Some(path_params) => path_params,
+                None => return Ok(None),
+            };
+        Ok(Some(RouteMatch {
+            handler: self
+                // Without a request to negotiate with, there may be no handler
+                .select_handler(py, leaf, scope_type, method, &Wants::default())?
+                .ok()
+                .flatten()
+                .map(|(handler, _)| handler),
+            path_params: path_params.into(),
+            raw_path_params: params::raw_path_params(py, &leaf.params, &params)?.into(),
+            route_path: String::from(route.path()),
+            path: String::from(route.split_mount(path).map_or(path, |(_, rest)| rest)),
+            allowed_methods: self
+                .allowed_methods(leaf)
+                .into_iter()
+                .map(String::from)
+                .collect(),
+        }))
+    }
+
+    /// Handle scopes of type `scope_type` with `app`, instead of any app they were
+    /// handled by before
+    ///
+    /// This is for types of scope which aren't routed by path, like `lifespan` or those
+    /// of ASGI extensions. Scopes of any other type which has no handler raise an
+    /// `UnsupportedScopeTypeException`.
+    #[pyo3(text_signature = "(scope_type, app)")]
+    fn add_scope_handler(
+        &mut self,
+        py: Python<'_>,
+        scope_type: &str,
+        app: Py<ASGIApp>,
+    ) -> PyResult<()> {
+        if is_routed(scope_type) {
+            return Err(self.exceptions.improperly_configured(
+                py,
+                format!("Scopes of type {:?} are routed by path", scope_type),
+            ));
+        }
+        self.scope_handlers.insert(String::from(scope_type), app);
+        Ok(())
+    }
+
+    /// Route requests to hosts matching `pattern` with `other`, instead of this map
+    ///
+    /// Host patterns are tried in the order they were added, and requests to any other
+    /// host use this map's own routes. Parameters in the pattern are added to the
+    /// request's `path_params`. `other` can't route any host back to this map.
+    #[pyo3(text_signature = "(pattern, other)")]
+    fn add_host(mut slf: PyRefMut<'_, Self>, pattern: &str, other: Py<RouteMap>) -> PyResult<()> {
+        let py = slf.py();
+        if routes_to(py, &other, slf.as_ptr()) {
+            return Err(slf.exceptions.improperly_configured(
+                py,
+                format!("Host {:?} can't be routed back to the same map", pattern),
+            ));
+        }
+        let host_pattern = HostPattern::new(pattern).map_err(|e| {
+            slf.exceptions
+                .improperly_configured(py, format!("Invalid host pattern {:?}: {}", pattern, e))
+        })?;
+        if slf
+            .hosts
+            .iter()
+            .any(|(existing, _)| *existing == host_pattern)
+        {
+            return Err(slf.exceptions.improperly_configured(
+                py,
+                format!("Host pattern {:?} is already routed", pattern),
+            ));
+        }
+        slf.hosts.push((host_pattern, other));
+        Ok(())
+    }
+
+    /// Mount an ASGI app at `prefix`, to handle any path below it
+    ///
+    /// The app sees the rest of the path, with `prefix` added to its `root_path`.
+    #[pyo3(text_signature = "(prefix, app)")]
+    fn mount(&mut self, py: Python<'_>, prefix: &str, app: Py<ASGIApp>) -> PyResult<()> {
+        self.mount_(py, prefix, app)
+    }
+
+    /// Include all the routes of `other` below `prefix`
+    ///
+    /// The routes are copied, so changes made to `other` afterwards don't affect this
+    /// map. Their handler names can be used with `url_path_for` on this map.
+    #[pyo3(text_signature = "(prefix, other)")]
+    fn include(
+        &mut self,
+        py: Python<'_>,
+        prefix: &str,
+        other: PyRef<'_, RouteMap>,
+    ) -> PyResult<()> {
+        self.include_(py, prefix, &other)
+    }
+
+    /// Build the path of the route with a handler named `name`, filling in its
+    /// path parameters from `params`
+    ///
+    /// Raises `NoRouteMatchFoundException` if the path wouldn't be routed back to that
+    /// route with the same parameters, e.g. for a value not matching its pattern.
+    #[pyo3(text_signature = "(name, **params)")]
+    #[args(params = "**")]
+    fn url_path_for(
+        &self,
+        py: Python<'_>,
+        name: &str,
+        params: Option<&PyDict>,
+    ) -> PyResult<String> {
+        let templates = self.names.get(name).ok_or_else(|| {
+            self.exceptions
+                .no_route_match_found(py, format!("No route named {:?}", name))
+        })?;
+        // A handler can be registered on several paths, pick the one taking these params
+        let template = templates
+            .iter()
+            .find(|template| template.accepts(params))
+            .unwrap_or(&templates[0]);
+        template.render(
+            py,
+            name,
+            params,
+            self.options.match_raw_path,
+            &self.exceptions,
+        )
+    }
+}
+
+/// Whether scopes of type `scope_type` are routed by their path
+fn is_routed(scope_type: &str) -> bool {
+    matches!(scope_type, "http" | "websocket")
+}
+
+/// Whether `routes` is the route map at `target`, or routes some host to it
+///
+/// Only maps which aren't `target` are borrowed, since it may be being changed.
+fn routes_to(py: Python<'_>, routes: &Py<RouteMap>, target: *mut pyo3::ffi::PyObject) -> bool {
+    routes.as_ptr() == target
+        || routes
+            .borrow(py)
+            .hosts
+            .iter()
+            .any(|(_, other)| routes_to(py, other, target))
+}
+
+/// Add the parameters of a request's host to its `path_params`, unless there's a path
+/// parameter with the same name
+fn add_host_params(path_params: &PyDict, host_params: &[(&str, &str)]) -> PyResult<()> {
+    for &(name, value) in host_params {
+        if !path_params.contains(name)? {
+            path_params.set_item(name, value)?;
+        }
+    }
+    Ok(())
+}
+
+/// The result of `RouteMap.match`
+#[pyclass(module = "starlite_router")]
+#[derive(Debug)]
+struct RouteMatch {
+    /// The handler for the method/scope type, if there is one
+    #[pyo3(get)]
+    handler: Option<Py<ASGIApp>>,
+    #[pyo3(get)]
+    path_params: Py<PyDict>,
+    #[pyo3(get)]
+    raw_path_params: Py<PyDict>,
+    /// The path of the matched route, as registered
+    #[pyo3(get)]
+    route_path: String,
+    /// The path the handler will see, with any mount's prefix removed
+    #[pyo3(get)]
+    path: String,
+    #[pyo3(get)]
+    allowed_methods: Vec<String>,
+}
+
+#[pymethods]
+impl RouteMatch {
+    fn __repr__(&self) -> String {
+        format!("{:#?}", self)
+    }
 }
 
 /// A Python module implemented in Rust.
 #[pymodule]
-fn starlite_router(_p: Python, m: &PyModule) -> PyResult<()> {
+fn starlite_router(py: Python, m: &PyModule) -> PyResult<()> {
     m.add_class::<RouteMap>()?;
+    m.add_class::<RouteMatch>()?;
+    m.add_class::<routes::HTTPRoute>()?;
+    m.add_class::<routes::WebSocketRoute>()?;
+    m.add_class::<routes::ASGIRoute>()?;
+    exceptions::add_to_module(py, m)?;
     Ok(())
 }
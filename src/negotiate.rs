@@ -0,0 +1,70 @@
+use pyo3::prelude::*;
+use pyo3::types::{PyDict, PyMapping, PyString};
+use starlite_router_core::negotiate::{HeaderPredicates, MediaType};
+
+/// The `headers` of an ASGI scope
+pub(crate) type Headers<'a> = Vec<(&'a [u8], &'a [u8])>;
+
+pub(crate) fn scope_headers(scope: &PyMapping) -> PyResult<Headers<'_>> {
+    match scope.get_item(pyo3::intern!(scope.py(), "headers")) {
+        Ok(headers) => headers.iter()?.map(|header| header?.extract()).collect(),
+        Err(_) => Ok(Vec::new()),
+    }
+}
+
+/// The subprotocols a websocket scope asks for, most preferred first
+pub(crate) fn scope_subprotocols(scope: &PyMapping) -> PyResult<Vec<String>> {
+    match scope.get_item(pyo3::intern!(scope.py(), "subprotocols")) {
+        Ok(subprotocols) => subprotocols.extract(),
+        Err(_) => Ok(Vec::new()),
+    }
+}
+
+/// The predicates declared as the `match` entry of a route handler's `opt`, if there
+/// are any:
+///
+/// ```python
+/// opt={"match": {
+///     "accept": ["application/json"],  # Media types the handler responds with
+///     "content_type": "application/json",  # Media types it accepts
+///     "headers": {"x-api-version": "2", "x-debug": None},  # None for any value
+///     "subprotocols": ["graphql-ws"],  # Websocket subprotocols it speaks
+/// }}
+/// ```
+pub(crate) fn opt_predicates(opt: &PyAny) -> PyResult<Option<HeaderPredicates>> {
+    let spec: &PyDict = match opt.downcast::<PyDict>()?.get_item("match") {
+        Some(spec) => spec.downcast()?,
+        None => return Ok(None),
+    };
+
+    let strings = |key: &str| -> PyResult<Vec<String>> {
+        match spec.get_item(key) {
+            None => Ok(Vec::new()),
+            Some(value) if value.is_instance_of::<PyString>()? => Ok(vec![value.extract()?]),
+            Some(value) => value.extract(),
+        }
+    };
+    let media_types = |key: &str| -> PyResult<Vec<MediaType>> {
+        Ok(strings(key)?
+            .iter()
+            .map(|name| MediaType::parse(name))
+            .collect())
+    };
+    let headers = match spec.get_item("headers") {
+        Some(headers) => headers
+            .downcast::<PyDict>()?
+            .iter()
+            .map(|(name, value)| {
+                let name: String = name.extract()?;
+                Ok((name.to_ascii_lowercase(), value.extract()?))
+            })
+            .collect::<PyResult<_>>()?,
+        None => Vec::new(),
+    };
+    Ok(Some(HeaderPredicates {
+        accept: media_types("accept")?,
+        content_type: media_types("content_type")?,
+        headers,
+        subprotocols: strings("subprotocols")?,
+    }))
+}
@@ -0,0 +1,237 @@
+use pyo3::exceptions::{PyTypeError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::{
+    IntoPyDict, PyDate, PyDateTime, PyDelta, PyDict, PyFloat, PyLong, PyString, PyTime, PyType,
+};
+use starlite_router_core::param::{
+    is_decimal, is_int, parse_date, parse_datetime, parse_duration, parse_float, parse_time,
+    parse_uuid, split_duration, DateTime, Time,
+};
+use std::borrow::Cow;
+
+/// Python types path parameters can be declared with, which we know how to
+/// convert without calling back into Python to parse the value
+#[derive(Debug, Clone)]
+pub(crate) struct ParamTypes {
+    uuid: Py<PyType>,
+    decimal: Py<PyType>,
+    path: Py<PyType>,
+    // pyo3's `PyDateTime` type object is actually `date`, so look these up ourselves
+    date: Py<PyType>,
+    datetime: Py<PyType>,
+    time: Py<PyType>,
+    timedelta: Py<PyType>,
+    timezone: Py<PyType>,
+}
+
+impl ParamTypes {
+    pub(crate) fn new(py: Python<'_>) -> PyResult<Self> {
+        let extract_type = |module: &str, name: &str| -> PyResult<Py<PyType>> {
+            let any: &PyAny = py.import(module)?.getattr(name)?;
+            Ok(any.downcast::<PyType>()?.into())
+        };
+        Ok(Self {
+            uuid: extract_type("uuid", "UUID")?,
+            decimal: extract_type("decimal", "Decimal")?,
+            path: extract_type("pathlib", "Path")?,
+            date: extract_type("datetime", "date")?,
+            datetime: extract_type("datetime", "datetime")?,
+            time: extract_type("datetime", "time")?,
+            timedelta: extract_type("datetime", "timedelta")?,
+            timezone: extract_type("datetime", "timezone")?,
+        })
+    }
+}
+
+/// How the raw string value of a path parameter is converted
+#[derive(Debug)]
+pub(crate) enum Converter {
+    Str,
+    Int,
+    Float,
+    Uuid,
+    Decimal,
+    Date,
+    DateTime,
+    Time,
+    TimeDelta,
+    Path,
+    /// Any other type: call it with the raw string
+    Other(Py<PyAny>),
+}
+
+impl Converter {
+    fn for_type(ty: &PyAny, types: &ParamTypes) -> Self {
+        let py = ty.py();
+        if ty.is(py.get_type::<PyString>()) {
+            Self::Str
+        } else if ty.is(py.get_type::<PyLong>()) {
+            Self::Int
+        } else if ty.is(py.get_type::<PyFloat>()) {
+            Self::Float
+        } else if ty.is(types.uuid.as_ref(py)) {
+            Self::Uuid
+        } else if ty.is(types.decimal.as_ref(py)) {
+            Self::Decimal
+        } else if ty.is(types.datetime.as_ref(py)) {
+            Self::DateTime
+        } else if ty.is(types.date.as_ref(py)) {
+            Self::Date
+        } else if ty.is(types.time.as_ref(py)) {
+            Self::Time
+        } else if ty.is(types.timedelta.as_ref(py)) {
+            Self::TimeDelta
+        } else if ty.is(types.path.as_ref(py)) {
+            Self::Path
+        } else {
+            Self::Other(ty.into())
+        }
+    }
+
+    /// Convert `value`, returning `None` if it isn't valid for this converter
+    fn convert(
+        &self,
+        py: Python<'_>,
+        types: &ParamTypes,
+        value: &str,
+    ) -> PyResult<Option<PyObject>> {
+        let result: PyResult<PyObject> = match self {
+            Self::Str => return Ok(Some(value.to_object(py))),
+            Self::Int if is_int(value) => match value.parse::<i64>() {
+                Ok(i) => return Ok(Some(i.to_object(py))),
+                // Too big for an i64, or grouped with underscores, let python deal with it
+                Err(_) => py.get_type::<PyLong>().call1((value,)).map(Into::into),
+            },
+            Self::Int => return Ok(None),
+            Self::Float => return Ok(parse_float(value).map(|f| f.to_object(py))),
+            Self::Uuid => match parse_uuid(value) {
+                Some(int) => {
+                    let kwargs = [("int", int)].into_py_dict(py);
+                    types.uuid.as_ref(py).call((), Some(kwargs)).map(Into::into)
+                }
+                None => return Ok(None),
+            },
+            Self::Decimal if is_decimal(value) => {
+                types.decimal.as_ref(py).call1((value,)).map(Into::into)
+            }
+            Self::Decimal => return Ok(None),
+            Self::Date => match parse_date(value.as_bytes()) {
+                Some((year, month, day)) => PyDate::new(py, year, month, day).map(Into::into),
+                None => return Ok(None),
+            },
+            Self::DateTime => match parse_datetime(value.as_bytes()) {
+                Some(dt) => datetime_to_object(py, types, &dt),
+                None => return Ok(None),
+            },
+            Self::Time => match parse_time(value.as_bytes()) {
+                Some(time) => time_to_object(py, types, &time),
+                None => return Ok(None),
+            },
+            Self::TimeDelta => match parse_duration(value).and_then(split_duration) {
+                Some((days, seconds, microseconds)) => {
+                    PyDelta::new(py, days, seconds, microseconds, false).map(Into::into)
+                }
+                None => return Ok(None),
+            },
+            Self::Path => types.path.as_ref(py).call1((value,)).map(Into::into),
+            Self::Other(ty) => ty.call1(py, (value,)),
+        };
+        match result {
+            Ok(obj) => Ok(Some(obj)),
+            Err(e)
+                if e.is_instance_of::<PyValueError>(py) || e.is_instance_of::<PyTypeError>(py) =>
+            {
+                Ok(None)
+            }
+            Err(e) => Err(e),
+        }
+    }
+}
+
+/// A path parameter, as declared in a route's `path_parameters`
+#[derive(Debug)]
+pub(crate) struct PathParam {
+    name: Py<PyString>,
+    converter: Converter,
+}
+
+impl PathParam {
+    pub(crate) fn from_definition(definition: &PyAny, types: &ParamTypes) -> PyResult<Self> {
+        let py = definition.py();
+        let name: &PyString = definition.get_item(pyo3::intern!(py, "name"))?.downcast()?;
+        let ty = definition.get_item(pyo3::intern!(py, "type"))?;
+        Ok(Self {
+            name: name.into(),
+            converter: Converter::for_type(ty, types),
+        })
+    }
+}
+
+/// Build the `path_params` dict from the raw `values` of `params`
+///
+/// Returns `None` if any of the values can't be converted to its parameter's type.
+pub(crate) fn parse_path_params<'py>(
+    py: Python<'py>,
+    types: &ParamTypes,
+    params: &[PathParam],
+    values: &[Cow<'_, str>],
+) -> PyResult<Option<&'py PyDict>> {
+    let dict = PyDict::new(py);
+    for (param, value) in params.iter().zip(values) {
+        match param.converter.convert(py, types, value)? {
+            Some(obj) => dict.set_item(param.name.as_ref(py), obj)?,
+            None => return Ok(None),
+        }
+    }
+    Ok(Some(dict))
+}
+
+/// Build a dict of the raw, unconverted `values` of `params`
+pub(crate) fn raw_path_params<'py>(
+    py: Python<'py>,
+    params: &[PathParam],
+    values: &[Cow<'_, str>],
+) -> PyResult<&'py PyDict> {
+    let dict = PyDict::new(py);
+    for (param, value) in params.iter().zip(values) {
+        dict.set_item(param.name.as_ref(py), value.as_ref())?;
+    }
+    Ok(dict)
+}
+
+/// Convert a parsed datetime to a `datetime.datetime`
+fn datetime_to_object(py: Python<'_>, types: &ParamTypes, dt: &DateTime) -> PyResult<PyObject> {
+    let tzinfo = tzinfo(py, types, &dt.time)?;
+    let (year, month, day) = dt.date;
+    let (hour, minute, second, microsecond) = dt.time.hms;
+    let dt = PyDateTime::new(
+        py,
+        year,
+        month,
+        day,
+        hour,
+        minute,
+        second,
+        microsecond,
+        tzinfo.as_ref(),
+    )?;
+    Ok(dt.into())
+}
+
+/// Convert a parsed time to a `datetime.time`
+fn time_to_object(py: Python<'_>, types: &ParamTypes, time: &Time) -> PyResult<PyObject> {
+    let tzinfo = tzinfo(py, types, time)?;
+    let (hour, minute, second, microsecond) = time.hms;
+    let time = PyTime::new(py, hour, minute, second, microsecond, tzinfo.as_ref())?;
+    Ok(time.into())
+}
+
+fn tzinfo(py: Python<'_>, types: &ParamTypes, time: &Time) -> PyResult<Option<PyObject>> {
+    match time.offset {
+        Some(offset) => {
+            let delta = PyDelta::new(py, 0, offset, 0, true)?;
+            Ok(Some(types.timezone.as_ref(py).call1((delta,))?.into()))
+        }
+        None => Ok(None),
+    }
+}
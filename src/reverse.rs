@@ -0,0 +1,206 @@
+use crate::exceptions::Exceptions;
+use ahash::AHashSet as HashSet;
+use percent_encoding::{utf8_percent_encode, AsciiSet};
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::types::{PyBool, PyDict, PyString, PyType};
+use starlite_router_core::normalize::{PathNormalization, RawPath};
+use starlite_router_core::segment::{param_spec, split_pieces, SEGMENT};
+use starlite_router_core::Router;
+
+/// Characters which must be escaped in a `path` parameter, which may span segments
+const PATH: &AsciiSet = &SEGMENT.remove(b'/');
+
+/// A route's path, which can be rendered with values for its parameters
+#[derive(Debug)]
+pub(crate) struct RouteTemplate {
+    pub(crate) path: String,
+    pieces: Vec<TemplatePiece>,
+    /// A router with only this route, to check rendered paths would be routed to it
+    route: Router<()>,
+}
+
+#[derive(Debug)]
+enum TemplatePiece {
+    Literal(String),
+    Param {
+        name: String,
+        ty: Py<PyType>,
+        catch_all: bool,
+    },
+}
+
+impl RouteTemplate {
+    pub(crate) fn new(
+        py: Python<'_>,
+        path: &str,
+        path_parameters: &[&PyAny],
+        param_strings: &HashSet<&str>,
+    ) -> PyResult<Self> {
+        let mut pieces = Vec::new();
+        let mut last_end = 0;
+        for (start, end, full) in split_pieces(path, |full| param_strings.contains(full)) {
+            if start > last_end {
+                pieces.push(TemplatePiece::Literal(String::from(&path[last_end..start])));
+            }
+            for &definition in path_parameters {
+                let definition_full: &str =
+                    definition.get_item(pyo3::intern!(py, "full"))?.extract()?;
+                if definition_full == full {
+                    pieces.push(TemplatePiece::Param {
+                        name: definition.get_item(pyo3::intern!(py, "name"))?.extract()?,
+                        ty: definition
+                            .get_item(pyo3::intern!(py, "type"))?
+                            .downcast::<PyType>()?
+                            .into(),
+                        catch_all: param_spec(full) == Some("path"),
+                    });
+                    break;
+                }
+            }
+            last_end = end;
+        }
+        if last_end < path.len() {
+            pieces.push(TemplatePiece::Literal(String::from(&path[last_end..])));
+        }
+        let mut route = Router::default();
+        route
+            .get_or_insert_with(path, |full| param_strings.contains(full), false, || ())
+            .map_err(|e| PyValueError::new_err(e.to_string()))?;
+        Ok(Self {
+            path: String::from(path),
+            pieces,
+            route,
+        })
+    }
+
+    fn param_names(&self) -> impl Iterator<Item = &str> {
+        self.pieces.iter().filter_map(|piece| match piece {
+            TemplatePiece::Param { name, .. } => Some(name.as_str()),
+            TemplatePiece::Literal(_) => None,
+        })
+    }
+
+    /// Whether `params` has exactly the parameters of this template
+    pub(crate) fn accepts(&self, params: Option<&PyDict>) -> bool {
+        let names: Vec<&str> = self.param_names().collect();
+        names.len() == params.map_or(0, PyDict::len)
+            && names
+                .iter()
+                .all(|&name| params.is_some_and(|params| params.contains(name).unwrap_or(false)))
+    }
+
+    /// Render the path, with each parameter replaced by its percent-encoded value
+    ///
+    /// The values must be such that the path is routed back to this route, with the
+    /// same values: of its parameters' types, matching their patterns, and without
+    /// slashes unless the parameter is of type `path` or `match_raw_path` is set.
+    pub(crate) fn render(
+        &self,
+        py: Python<'_>,
+        name: &str,
+        params: Option<&PyDict>,
+        match_raw_path: bool,
+        exceptions: &Exceptions,
+    ) -> PyResult<String> {
+        for key in params.into_iter().flat_map(PyDict::keys) {
+            let key: &str = key.extract()?;
+            if !self.param_names().any(|param_name| param_name == key) {
+                return Err(exceptions.no_route_match_found(
+                    py,
+                    format!("Unknown path parameter {:?} for route {:?}", key, name),
+                ));
+            }
+        }
+        let mut path = String::new();
+        // As it will be routed, once decoded
+        let mut decoded = String::new();
+        let mut values = Vec::new();
+        for piece in &self.pieces {
+            let (param_name, ty, catch_all) = match piece {
+                TemplatePiece::Literal(text) => {
+                    path.push_str(text);
+                    decoded.push_str(text);
+                    continue;
+                }
+                TemplatePiece::Param {
+                    name,
+                    ty,
+                    catch_all,
+                } => (name, ty, *catch_all),
+            };
+            let value = params
+                .and_then(|params| params.get_item(param_name))
+                .ok_or_else(|| {
+                    exceptions.no_route_match_found(
+                        py,
+                        format!(
+                            "Missing path parameter {:?} for route {:?}",
+                            param_name, name
+                        ),
+                    )
+                })?;
+            let ty = ty.as_ref(py);
+            // `bool` subclasses `int`, but `True` isn't a valid int parameter
+            let bool_for_int = value.is_instance_of::<PyBool>()? && !ty.is(py.get_type::<PyBool>());
+            let type_ok = (value.is_instance(ty)? && !bool_for_int)
+                || (catch_all && value.is_instance_of::<PyString>()?);
+            if !type_ok {
+                return Err(pyo3::exceptions::PyTypeError::new_err(format!(
+                    "Path parameter {:?} for route {:?} must be of type {}, got {}",
+                    param_name,
+                    name,
+                    ty.name()?,
+                    value.get_type().name()?,
+                )));
+            }
+            let value = value.str()?.to_str()?;
+            let set = if catch_all { PATH } else { SEGMENT };
+            path.extend(utf8_percent_encode(value, set));
+            decoded.push_str(value);
+            values.push(value);
+        }
+        let routed_values = if match_raw_path {
+            self.raw_routed_values(&path)
+        } else {
+            self.routed_values(&decoded)
+        };
+        if routed_values.is_none_or(|routed| routed != values) {
+            return Err(exceptions.no_route_match_found(
+                py,
+                format!(
+                    "Path parameters for route {:?} don't match its path {:?}",
+                    name, self.path
+                ),
+            ));
+        }
+        Ok(path)
+    }
+
+    /// The values of the parameters of this route for a request to `path`, if it's
+    /// routed here
+    fn routed_values(&self, path: &str) -> Option<Vec<String>> {
+        let found = self.route.find(path)?;
+        Some(
+            found
+                .params
+                .iter()
+                .map(|p| String::from(p.as_ref()))
+                .collect(),
+        )
+    }
+
+    /// Like `routed_values`, for a path which is matched before it's decoded
+    fn raw_routed_values(&self, path: &str) -> Option<Vec<String>> {
+        let raw =
+            RawPath::decode(PathNormalization::PassThrough, path.as_bytes(), |_| false).ok()?;
+        let found = self.route.find_raw(&raw)?;
+        Some(
+            found
+                .params
+                .iter()
+                .map(|p| String::from(p.as_ref()))
+                .collect(),
+        )
+    }
+}
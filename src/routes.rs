@@ -0,0 +1,208 @@
+use pyo3::prelude::*;
+use pyo3::types::{PyDict, PyList, PyType};
+use starlite_router_core::segment::{param_spec, split_pieces};
+
+/// The route classes a `RouteMap` accepts routes of
+#[derive(Debug, Clone)]
+pub(crate) struct RouteTypes {
+    pub(crate) http: Py<PyType>,
+    pub(crate) websocket: Py<PyType>,
+    pub(crate) asgi: Py<PyType>,
+}
+
+impl RouteTypes {
+    /// Get the route classes for a `RouteMap`, with any in `overrides` replacing the
+    /// defaults
+    ///
+    /// The defaults are starlite's route classes if it's installed, otherwise our own.
+    pub(crate) fn new(py: Python<'_>, overrides: Option<&PyDict>) -> PyResult<Self> {
+        let mut route_types = match py.import("starlite.routes") {
+            Ok(module) => {
+                let extract_type = |name: &str| -> PyResult<Py<PyType>> {
+                    let any: &PyAny = module.getattr(name)?;
+                    Ok(any.downcast::<PyType>()?.into())
+                };
+                Self {
+                    http: extract_type("HTTPRoute")?,
+                    websocket: extract_type("WebSocketRoute")?,
+                    asgi: extract_type("ASGIRoute")?,
+                }
+            }
+            Err(e) if e.is_instance_of::<pyo3::exceptions::PyImportError>(py) => Self {
+                http: py.get_type::<HTTPRoute>().into(),
+                websocket: py.get_type::<WebSocketRoute>().into(),
+                asgi: py.get_type::<ASGIRoute>().into(),
+            },
+            Err(e) => return Err(e),
+        };
+        for (key, route_type) in overrides.into_iter().flatten() {
+            let field = match key.extract()? {
+                "http" => &mut route_types.http,
+                "websocket" => &mut route_types.websocket,
+                "asgi" => &mut route_types.asgi,
+                key => {
+                    return Err(pyo3::exceptions::PyValueError::new_err(format!(
+                        "Unknown route type {:?}, expected one of \"http\", \"websocket\" or \
+                         \"asgi\"",
+                        key
+                    )))
+                }
+            };
+            *field = route_type.downcast::<PyType>()?.into();
+        }
+        Ok(route_types)
+    }
+}
+
+/// A route with a handler for each of its http methods, for use without starlite
+#[pyclass(module = "starlite_router")]
+#[derive(Debug)]
+pub(crate) struct HTTPRoute {
+    #[pyo3(get)]
+    path: String,
+    #[pyo3(get)]
+    path_parameters: Py<PyList>,
+    /// The handler for each method, as `{method: (handler, None)}`
+    #[pyo3(get)]
+    route_handler_map: Py<PyDict>,
+    /// The name of the handlers, for `url_path_for`
+    #[pyo3(get)]
+    name: Option<String>,
+    /// Options for the handlers, like starlite's: `match` chooses between handlers by
+    /// request headers
+    #[pyo3(get)]
+    opt: Option<Py<PyDict>>,
+}
+
+#[pymethods]
+impl HTTPRoute {
+    #[new]
+    #[args(path, handlers, "*", name = "None", opt = "None")]
+    fn new(
+        py: Python<'_>,
+        path: String,
+        handlers: &PyDict,
+        name: Option<String>,
+        opt: Option<Py<PyDict>>,
+    ) -> PyResult<Self> {
+        let route_handler_map = PyDict::new(py);
+        for (method, handler) in handlers {
+            let method: &str = method.extract()?;
+            route_handler_map.set_item(method.to_ascii_uppercase(), (handler, py.None()))?;
+        }
+        Ok(Self {
+            path_parameters: path_parameters(py, &path)?.into(),
+            path,
+            route_handler_map: route_handler_map.into(),
+            name,
+            opt,
+        })
+    }
+}
+
+/// A route with a websocket handler, for use without starlite
+#[pyclass(module = "starlite_router")]
+#[derive(Debug)]
+pub(crate) struct WebSocketRoute {
+    #[pyo3(get)]
+    path: String,
+    #[pyo3(get)]
+    path_parameters: Py<PyList>,
+    #[pyo3(get)]
+    route_handler: PyObject,
+    /// The name of the handler, for `url_path_for`
+    #[pyo3(get)]
+    name: Option<String>,
+    /// Options for the handler, like starlite's: `match` chooses between handlers by
+    /// request headers and subprotocols
+    #[pyo3(get)]
+    opt: Option<Py<PyDict>>,
+}
+
+#[pymethods]
+impl WebSocketRoute {
+    #[new]
+    #[args(path, handler, "*", name = "None", opt = "None")]
+    fn new(
+        py: Python<'_>,
+        path: String,
+        handler: PyObject,
+        name: Option<String>,
+        opt: Option<Py<PyDict>>,
+    ) -> PyResult<Self> {
+        Ok(Self {
+            path_parameters: path_parameters(py, &path)?.into(),
+            path,
+            route_handler: handler,
+            name,
+            opt,
+        })
+    }
+}
+
+/// A route with an ASGI app handling any scope, for use without starlite
+#[pyclass(module = "starlite_router")]
+#[derive(Debug)]
+pub(crate) struct ASGIRoute {
+    #[pyo3(get)]
+    path: String,
+    #[pyo3(get)]
+    path_parameters: Py<PyList>,
+    #[pyo3(get)]
+    route_handler: PyObject,
+    /// The name of the handler, for `url_path_for`
+    #[pyo3(get)]
+    name: Option<String>,
+    /// Options for the handler, like starlite's: `match` chooses between handlers by
+    /// request headers
+    #[pyo3(get)]
+    opt: Option<Py<PyDict>>,
+}
+
+#[pymethods]
+impl ASGIRoute {
+    #[new]
+    #[args(path, handler, "*", name = "None", opt = "None")]
+    fn new(
+        py: Python<'_>,
+        path: String,
+        handler: PyObject,
+        name: Option<String>,
+        opt: Option<Py<PyDict>>,
+    ) -> PyResult<Self> {
+        Ok(Self {
+            path_parameters: path_parameters(py, &path)?.into(),
+            path,
+            route_handler: handler,
+            name,
+            opt,
+        })
+    }
+}
+
+/// The definitions of the parameters in `path`, like starlite's: a dict of the
+/// `name`, `full` text in braces and `type` of each
+fn path_parameters<'py>(py: Python<'py>, path: &str) -> PyResult<&'py PyList> {
+    let definitions = PyList::empty(py);
+    for (_, _, full) in split_pieces(path, |_| true) {
+        let (module, name) = match param_spec(full) {
+            Some("int") => ("builtins", "int"),
+            Some("float") => ("builtins", "float"),
+            Some("uuid") => ("uuid", "UUID"),
+            Some("decimal") => ("decimal", "Decimal"),
+            Some("date") => ("datetime", "date"),
+            Some("datetime") => ("datetime", "datetime"),
+            Some("time") => ("datetime", "time"),
+            Some("timedelta") => ("datetime", "timedelta"),
+            Some("path") => ("pathlib", "Path"),
+            // Anything else is a string, maybe constrained by a regex
+            _ => ("builtins", "str"),
+        };
+        let definition = PyDict::new(py);
+        definition.set_item("name", full.split(':').next().unwrap_or(full))?;
+        definition.set_item("full", full)?;
+        definition.set_item("type", py.import(module)?.getattr(name)?)?;
+        definitions.append(definition)?;
+    }
+    Ok(definitions)
+}
@@ -0,0 +1,93 @@
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::types::PyMapping;
+use starlite_router_core::version::{query_value, split_version_prefix, Version};
+
+/// Where the version a request wants is taken from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Versioning {
+    /// A `/v{version}` prefix on the path, which is moved to the `root_path`
+    PathPrefix,
+    /// A header, `accept-version` by default
+    Header(String),
+    /// A query parameter, `version` by default
+    Query(String),
+}
+
+impl Versioning {
+    pub(crate) fn from_options(strategy: &str, param: Option<&str>) -> PyResult<Self> {
+        match (strategy, param) {
+            ("path", None) => Ok(Self::PathPrefix),
+            ("path", Some(_)) => Err(PyValueError::new_err(
+                "version_param can't be used with path versioning",
+            )),
+            ("header", param) => Ok(Self::Header(
+                param.unwrap_or("accept-version").to_ascii_lowercase(),
+            )),
+            ("query", param) => Ok(Self::Query(String::from(param.unwrap_or("version")))),
+            _ => Err(PyValueError::new_err(format!(
+                "Unknown versioning strategy {:?}, expected one of \"path\", \"header\" or \
+                 \"query\"",
+                strategy
+            ))),
+        }
+    }
+
+    /// Get the version a request for `path` asks for, if it asks for one, or what it
+    /// asks for if that isn't a version
+    ///
+    /// With path versioning, the rest of the path after the version prefix is to be
+    /// routed, as if the routes were mounted there.
+    pub(crate) fn requested<'p>(
+        &self,
+        scope: &PyMapping,
+        path: &'p str,
+    ) -> PyResult<Result<Requested<'p>, String>> {
+        let py = scope.py();
+        let mut prefix = None;
+        let requested = match self {
+            Self::PathPrefix => match split_version_prefix(path) {
+                Some((version_prefix, _)) => {
+                    prefix = Some(version_prefix);
+                    String::from(&version_prefix[1..])
+                }
+                None => return Ok(Ok(Requested::default())),
+            },
+            Self::Header(name) => {
+                let headers = crate::negotiate::scope_headers(scope)?;
+                let value = headers
+                    .iter()
+                    .find(|(header, _)| header.eq_ignore_ascii_case(name.as_bytes()));
+                match value {
+                    Some((_, value)) => String::from_utf8_lossy(value).into_owned(),
+                    None => return Ok(Ok(Requested::default())),
+                }
+            }
+            Self::Query(param) => {
+                let query_string: &[u8] = match scope.get_item(pyo3::intern!(py, "query_string")) {
+                    Ok(query_string) => query_string.extract()?,
+                    Err(_) => return Ok(Ok(Requested::default())),
+                };
+                match query_value(query_string, param) {
+                    Some(value) => value,
+                    None => return Ok(Ok(Requested::default())),
+                }
+            }
+        };
+        Ok(match Version::parse(&requested) {
+            Some(version) => Ok(Requested {
+                version: Some(version),
+                prefix,
+            }),
+            None => Err(requested),
+        })
+    }
+}
+
+/// The API version a request asks for
+#[derive(Debug, Default)]
+pub(crate) struct Requested<'p> {
+    pub(crate) version: Option<Version>,
+    /// The `/v{version}` prefix of the path which asked for it, with path versioning
+    pub(crate) prefix: Option<&'p str>,
+}
@@ -10,10 +10,131 @@ Send = typing.Callable[[Message], typing.Awaitable[None]]
 ASGIApp = typing.Callable[[Scope, Receive, Send], typing.Awaitable[None]]
 
 
+class ImproperlyConfiguredException(Exception): ...
+class NotFoundException(Exception): ...
+
+class MethodNotAllowedException(Exception):
+    allowed_methods: typing.List[str]
+
+class NoRouteMatchFoundException(Exception): ...
+class ValidationException(Exception): ...
+class NotAcceptableException(Exception): ...
+class UnsupportedScopeTypeException(Exception): ...
+class VersionNotAvailableException(NotFoundException): ...
+
+class HTTPRoute:
+    path: str
+    path_parameters: typing.List[typing.Dict[str, typing.Any]]
+    route_handler_map: typing.Dict[str, typing.Tuple[ASGIApp, None]]
+    name: typing.Optional[str]
+    opt: typing.Optional[typing.Dict[str, typing.Any]]
+
+    def __init__(
+        self,
+        path: str,
+        handlers: typing.Mapping[str, ASGIApp],
+        *,
+        name: typing.Optional[str] = None,
+        opt: typing.Optional[typing.Dict[str, typing.Any]] = None,
+    ): ...
+
+class WebSocketRoute:
+    path: str
+    path_parameters: typing.List[typing.Dict[str, typing.Any]]
+    route_handler: ASGIApp
+    name: typing.Optional[str]
+    opt: typing.Optional[typing.Dict[str, typing.Any]]
+
+    def __init__(
+        self,
+        path: str,
+        handler: ASGIApp,
+        *,
+        name: typing.Optional[str] = None,
+        opt: typing.Optional[typing.Dict[str, typing.Any]] = None,
+    ): ...
+
+class ASGIRoute:
+    path: str
+    path_parameters: typing.List[typing.Dict[str, typing.Any]]
+    route_handler: ASGIApp
+    name: typing.Optional[str]
+    opt: typing.Optional[typing.Dict[str, typing.Any]]
+
+    def __init__(
+        self,
+        path: str,
+        handler: ASGIApp,
+        *,
+        name: typing.Optional[str] = None,
+        opt: typing.Optional[typing.Dict[str, typing.Any]] = None,
+    ): ...
+
+class RouteMatch:
+    handler: typing.Optional[ASGIApp]
+    path_params: typing.Dict[str, typing.Any]
+    raw_path_params: typing.Dict[str, str]
+    route_path: str
+    path: str
+    allowed_methods: typing.List[str]
 
 class RouteMap:
-    def __init__(self, app: typing.Any): ...
+    def __init__(
+        self,
+        app: typing.Any = None,
+        *,
+        auto_head: bool = False,
+        auto_options: bool = False,
+        trailing_slash: typing.Literal[
+            "lenient", "strict", "redirect", "redirect_permanent"
+        ] = "lenient",
+        path_normalization: typing.Literal[
+            "pass_through", "normalize", "reject"
+        ] = "pass_through",
+        match_raw_path: bool = False,
+        versioning: typing.Optional[typing.Literal["path", "header", "query"]] = None,
+        version_param: typing.Optional[str] = None,
+        route_types: typing.Optional[
+            typing.Mapping[typing.Literal["http", "websocket", "asgi"], type]
+        ] = None,
+        exceptions: typing.Optional[
+            typing.Mapping[str, typing.Callable[..., BaseException]]
+        ] = None,
+        not_found: typing.Optional[ASGIApp] = None,
+        method_not_allowed: typing.Optional[ASGIApp] = None,
+        lifespan: typing.Optional[ASGIApp] = None,
+    ): ...
 
-    def add_routes(self, routes: typing.Collection[BaseRoute]): ...
+    async def __call__(self, scope: Scope, receive: Receive, send: Send) -> None: ...
+
+    def add_routes(
+        self, routes: typing.Collection[BaseRoute], *, version: typing.Optional[str] = None
+    ): ...
+
+    def remove_route(
+        self, path: str, methods: typing.Optional[typing.Collection[str]] = None
+    ) -> None: ...
+
+    def replace_route(
+        self, route: BaseRoute, *, version: typing.Optional[str] = None
+    ) -> None: ...
 
     def resolve_route(self, scope: Scope) -> ASGIApp: ...
+
+    def match(
+        self,
+        path: str,
+        method: typing.Optional[str] = None,
+        scope_type: str = "http",
+        host: typing.Optional[str] = None,
+    ) -> typing.Optional[RouteMatch]: ...
+
+    def add_scope_handler(self, scope_type: str, app: ASGIApp) -> None: ...
+
+    def add_host(self, pattern: str, other: RouteMap) -> None: ...
+
+    def mount(self, prefix: str, app: ASGIApp) -> None: ...
+
+    def include(self, prefix: str, other: RouteMap) -> None: ...
+
+    def url_path_for(self, name: str, **params: typing.Any) -> str: ...